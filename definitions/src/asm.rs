@@ -0,0 +1,56 @@
+//! Layout of the asm backend's per-basic-block trace cache. The asm
+//! runtime indexes into `traces` with `calculate_slot` and dispatches
+//! through `thread`, so the field order and sizes here must match the
+//! hand-written assembly in the `ckb-vm-runtime` crate.
+
+pub const TRACE_ITEM_LENGTH: usize = 16;
+pub const TRACE_SIZE: usize = 16384;
+
+/// How many VM registers the dispatch loop can keep resident in host
+/// registers for a single trace. Small and fixed so the field below stays
+/// cheap to copy and the loop's register-cache logic stays a flat array
+/// scan rather than a real allocator.
+pub const HOT_REGISTER_CAPACITY: usize = 4;
+
+pub type Instruction = u64;
+
+#[derive(Clone, Copy)]
+pub struct Trace {
+    pub instructions: [Instruction; TRACE_ITEM_LENGTH],
+    pub thread: [u64; TRACE_ITEM_LENGTH],
+    pub cycles: u64,
+    pub address: u64,
+    pub length: u8,
+    /// The `hot_register_count` most-touched VM register indices in this
+    /// trace, most-touched first. Computed once when the trace is
+    /// assembled (see `ckb_vm::machine::asm::hot_registers::analyze`) and
+    /// reused on every re-execution of this slot: the dispatch loop loads
+    /// these into host registers at block entry instead of re-reading
+    /// `AsmCoreMachine::registers` on every instruction.
+    pub hot_registers: [u8; HOT_REGISTER_CAPACITY],
+    pub hot_register_count: u8,
+    /// Bitmask (bit `i` set means VM register `i`) of hot registers this
+    /// trace ever writes. Only dirty hot registers need writing back at
+    /// block boundaries / before `OP_CUSTOM_TRACE_END`.
+    pub dirty_mask: u32,
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self {
+            instructions: [0; TRACE_ITEM_LENGTH],
+            thread: [0; TRACE_ITEM_LENGTH],
+            cycles: 0,
+            address: 0,
+            length: 0,
+            hot_registers: [0; HOT_REGISTER_CAPACITY],
+            hot_register_count: 0,
+            dirty_mask: 0,
+        }
+    }
+}
+
+/// Maps a basic block's starting PC to a slot in the trace cache.
+pub fn calculate_slot(addr: u64) -> usize {
+    ((addr >> 1) as usize) & (TRACE_SIZE - 1)
+}
@@ -0,0 +1,38 @@
+//! Opcode numbers shared by the decoder, the interpreter and the asm
+//! backend's generated label table. An opcode doubles as an index into
+//! `ckb_vm_asm_labels`, so it must stay inside `u8` range.
+
+pub type InstructionOpcode = u16;
+
+pub const OP_UNLOADED: InstructionOpcode = 0x00;
+
+// Base RV32I/RV64I opcodes needed by the fusion rules and the examples
+// in the test suite.
+pub const OP_LUI: InstructionOpcode = 0x10;
+pub const OP_AUIPC: InstructionOpcode = 0x11;
+pub const OP_JAL: InstructionOpcode = 0x12;
+pub const OP_JALR: InstructionOpcode = 0x13;
+pub const OP_BEQ: InstructionOpcode = 0x14;
+pub const OP_ADDI: InstructionOpcode = 0x15;
+pub const OP_ADD: InstructionOpcode = 0x16;
+pub const OP_SLLI: InstructionOpcode = 0x17;
+pub const OP_LW: InstructionOpcode = 0x18;
+pub const OP_LD: InstructionOpcode = 0x19;
+pub const OP_ECALL: InstructionOpcode = 0x1a;
+
+// Custom, VM-internal opcodes. These never appear in a decoded program;
+// they are only produced by fusion/rewrite passes or used as markers.
+pub const OP_CUSTOM_LOAD_UIMM: InstructionOpcode = 0xf0;
+// auipc+addi -> rd = pc + sign_extend(imm_hi + imm_lo) in one step.
+pub const OP_CUSTOM_LOAD_IMM_ABS: InstructionOpcode = 0xf1;
+// lui+addi -> rd = sign_extend(imm_hi + imm_lo), independent of pc.
+pub const OP_CUSTOM_CONST32: InstructionOpcode = 0xf2;
+// slli+add -> rd = rs2 + (rs1 << shamt), the common scaled-index pattern.
+pub const OP_CUSTOM_INDEXED_ADDR: InstructionOpcode = 0xf3;
+// two back-to-back `lw`s from the same base at adjacent offsets -> one
+// 8-byte-apart double load producing both destination registers.
+pub const OP_CUSTOM_WIDE_LOAD32: InstructionOpcode = 0xf4;
+// two back-to-back `ld`s from the same base at adjacent offsets -> the
+// same idea, 16 bytes apart.
+pub const OP_CUSTOM_WIDE_LOAD64: InstructionOpcode = 0xf5;
+pub const OP_CUSTOM_TRACE_END: InstructionOpcode = 0xff;
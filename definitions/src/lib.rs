@@ -0,0 +1,8 @@
+//! Shared wire-format definitions used by both the interpreter and the
+//! generated asm backend. Keeping these in their own crate lets the asm
+//! runtime (which is partly hand-written assembly) and the Rust-side
+//! decoder/executor agree on a single layout without depending on the
+//! rest of `ckb-vm`.
+
+pub mod asm;
+pub mod instructions;
@@ -0,0 +1,118 @@
+//! A breakpoint/stepping layer over any `SupportMachine`, driving the same
+//! decode-then-execute loop used everywhere else but pausing to hand
+//! control back to an embedder instead of just running to completion.
+
+use std::collections::BTreeSet;
+
+use crate::instructions::{extract_opcode, Instruction, Itype};
+use crate::machine::SupportMachine;
+use crate::Error;
+use ckb_vm_definitions::instructions as insts;
+
+/// Called whenever [`Debugger::run`] pauses: at a breakpoint, after a
+/// single step, or when a pending `step_out` reaches its target depth.
+/// Implementors can read/write `machine`'s registers and memory from
+/// here to build a REPL-style inspector.
+pub trait OnBreak<Mac> {
+    fn on_break(&mut self, machine: &mut Mac);
+}
+
+/// Tracks breakpoints and call depth across an arbitrary number of
+/// `run` calls; doesn't own the machine, so the same `Debugger` can
+/// drive an interpreter machine or an asm one interchangeably as long as
+/// the caller supplies matching `decode`/`execute` closures.
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u64>,
+    call_depth: usize,
+    step_out_depth: Option<usize>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_breakpoint(&mut self, pc: u64) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: u64) {
+        self.breakpoints.remove(&pc);
+    }
+
+    pub fn call_depth(&self) -> usize {
+        self.call_depth
+    }
+
+    /// Arranges for the next `run(..., single_step: false)` to stop as
+    /// soon as `call_depth` drops below its current value, i.e. once the
+    /// current frame returns.
+    pub fn step_out(&mut self) {
+        self.step_out_depth = Some(self.call_depth);
+    }
+
+    /// Runs `machine` instruction by instruction via `decode`/`execute`
+    /// until it stops running, a breakpoint PC is reached, `single_step`
+    /// causes a pause after exactly one instruction, or a pending
+    /// `step_out` target depth is reached. Calls `handler.on_break` every
+    /// time it pauses (including the `single_step` case).
+    ///
+    /// Only `single_step` (and, by extension, a `step_out` hit while
+    /// `single_step` is set) makes `run` itself return control to the
+    /// caller: a plain breakpoint hit with `single_step: false` calls
+    /// `handler.on_break` synchronously and then keeps running. This is a
+    /// deliberate choice, not a gap — `Debugger` is meant to drive a
+    /// blocking, REPL-style embedder (inspect/mutate `machine` from inside
+    /// `on_break`, then return to let `run` continue), not to suspend a
+    /// coroutine. An embedder that wants `run` to return on every
+    /// breakpoint should call it with `single_step: true` and re-check its
+    /// own breakpoint set between steps.
+    pub fn run<Mac, D, X>(
+        &mut self,
+        machine: &mut Mac,
+        mut decode: D,
+        mut execute: X,
+        handler: &mut impl OnBreak<Mac>,
+        single_step: bool,
+    ) -> Result<(), Error>
+    where
+        Mac: SupportMachine,
+        D: FnMut(&mut Mac) -> Result<Instruction, Error>,
+        X: FnMut(&mut Mac, Instruction) -> Result<(), Error>,
+    {
+        while machine.running() {
+            let instruction = decode(machine)?;
+            self.observe(instruction);
+            execute(machine, instruction)?;
+
+            let step_out_hit = self
+                .step_out_depth
+                .is_some_and(|depth| self.call_depth < depth);
+            if step_out_hit {
+                self.step_out_depth = None;
+            }
+
+            if self.breakpoints.contains(machine.pc()) || single_step || step_out_hit {
+                handler.on_break(machine);
+                if single_step {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates `call_depth` from a just-executed instruction: `jal`/`jalr`
+    /// that write `ra` (`x1`) push a frame, `jalr x0, ra` (the common
+    /// return idiom) pops one.
+    fn observe(&mut self, instruction: Instruction) {
+        let opcode = extract_opcode(instruction);
+        let i = Itype(instruction);
+        if (opcode == insts::OP_JAL || opcode == insts::OP_JALR) && i.rd() == 1 {
+            self.call_depth += 1;
+        } else if opcode == insts::OP_JALR && i.rd() == 0 && i.rs1() == 1 {
+            self.call_depth = self.call_depth.saturating_sub(1);
+        }
+    }
+}
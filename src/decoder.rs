@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+
+use crate::instructions::{Btype, Instruction, Itype, Jtype, Rtype, Utype};
+use crate::{Error, Memory};
+use ckb_vm_definitions::instructions as insts;
+
+/// Decodes raw RV32I/RV64I machine code one instruction at a time. Holds no
+/// state beyond the ISA/version it was built for, so it is cheap to wrap
+/// (see [`fusion::FusionDecoder`]).
+pub struct Decoder {
+    isa: u8,
+    version: u32,
+}
+
+impl Decoder {
+    pub fn decode<M: Memory>(&mut self, memory: &mut M, pc: u64) -> Result<Instruction, Error> {
+        let word = memory.load32(pc)?;
+        decode_word(word).ok_or(Error::InvalidInstruction((word & 0x7f) as u16))
+    }
+
+    pub fn isa(&self) -> u8 {
+        self.isa
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Decodes the instruction at `backend`'s current PC. Equivalent to
+    /// `self.decode(backend.memory_mut(), backend.pc())`, but works the
+    /// same for any [`crate::hal::VmBackend`] (the default machine, the
+    /// asm machine, or a third-party one).
+    pub fn decode_backend<B: crate::hal::VmBackend>(
+        &mut self,
+        backend: &mut B,
+    ) -> Result<Instruction, Error> {
+        crate::hal::fetch(backend, |mem, pc| self.decode(mem, pc))
+    }
+}
+
+/// Builds a [`Decoder`] for the given ISA flags/version. `R` mirrors the
+/// machine's register width (`u32` or `u64`) so callers can pick the right
+/// decoder with `build_decoder::<u64>(...)` without threading a machine
+/// type through.
+pub fn build_decoder<R>(isa: u8, version: u32) -> Decoder {
+    let _ = PhantomData::<R>;
+    Decoder { isa, version }
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+fn decode_word(word: u32) -> Option<Instruction> {
+    let opcode7 = word & 0x7f;
+    let rd = ((word >> 7) & 0x1f) as u8;
+    let funct3 = (word >> 12) & 0x7;
+    let rs1 = ((word >> 15) & 0x1f) as u8;
+    let rs2 = ((word >> 20) & 0x1f) as u8;
+    let funct7 = (word >> 25) & 0x7f;
+
+    let inst = match opcode7 {
+        0x37 => Utype::new(insts::OP_LUI, rd, word & 0xffff_f000).0,
+        0x17 => Utype::new(insts::OP_AUIPC, rd, word & 0xffff_f000).0,
+        0x6f => {
+            let imm20 = (word >> 31) & 0x1;
+            let imm10_1 = (word >> 21) & 0x3ff;
+            let imm11 = (word >> 20) & 0x1;
+            let imm19_12 = (word >> 12) & 0xff;
+            let raw = (imm20 << 20) | (imm19_12 << 12) | (imm11 << 11) | (imm10_1 << 1);
+            Jtype::new(insts::OP_JAL, rd, sign_extend(raw, 21)).0
+        }
+        0x67 if funct3 == 0 => {
+            Itype::new(insts::OP_JALR, rd, rs1, sign_extend(word >> 20, 12)).0
+        }
+        0x63 if funct3 == 0 => {
+            let imm12 = (word >> 31) & 0x1;
+            let imm10_5 = (word >> 25) & 0x3f;
+            let imm4_1 = (word >> 8) & 0xf;
+            let imm11 = (word >> 7) & 0x1;
+            let raw = (imm12 << 12) | (imm11 << 11) | (imm10_5 << 5) | (imm4_1 << 1);
+            Btype::new(insts::OP_BEQ, rs1, rs2, sign_extend(raw, 13)).0
+        }
+        0x03 if funct3 == 0x2 => {
+            Itype::new(insts::OP_LW, rd, rs1, sign_extend(word >> 20, 12)).0
+        }
+        0x03 if funct3 == 0x3 => {
+            Itype::new(insts::OP_LD, rd, rs1, sign_extend(word >> 20, 12)).0
+        }
+        0x13 if funct3 == 0x0 => {
+            Itype::new(insts::OP_ADDI, rd, rs1, sign_extend(word >> 20, 12)).0
+        }
+        0x13 if funct3 == 0x1 => {
+            // RV64I's shamt is 6 bits (bits 20..25); `rs2` above only kept
+            // the low 5 (bits 20..24), which would silently truncate
+            // shamt 32..63 instead of decoding it correctly.
+            let shamt = (word >> 20) & 0x3f;
+            Itype::new(insts::OP_SLLI, rd, rs1, shamt as i32).0
+        }
+        0x33 if funct3 == 0x0 && funct7 == 0x00 => Rtype::new(insts::OP_ADD, rd, rs1, rs2).0,
+        0x73 if word >> 7 == 0 => Itype::new(insts::OP_ECALL, 0, 0, 0).0,
+        _ => return None,
+    };
+
+    Some(crate::instructions::set_instruction_length_n(inst, 4))
+}
+
+pub mod fusion;
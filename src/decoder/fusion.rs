@@ -0,0 +1,195 @@
+use super::Decoder;
+use crate::instructions::{
+    extract_opcode, instruction_length, is_basic_block_end_instruction, set_instruction_length_n,
+    Instruction, Itype, Rtype, Utype,
+};
+use crate::{Error, Memory};
+use ckb_vm_definitions::instructions as insts;
+
+/// A macro-op fusion rule: given a lookahead window of already-decoded
+/// instructions (oldest first, at most [`WINDOW`] of them), optionally
+/// replace the prefix of the window it recognizes with a single fused
+/// instruction, plus how many bytes of the original stream it consumed.
+///
+/// Rules never see the PC: anything relative to it (e.g. `auipc`) must be
+/// folded at execute time by the fused opcode's handler, not baked in here.
+pub type FusionRule = fn(&[Instruction]) -> Option<(Instruction, u8)>;
+
+/// How many instructions ahead a rule may look. 2 covers every pair this
+/// module ships; widen it (and `FusionDecoder::decode`'s loop) if a future
+/// rule needs a third instruction of context.
+const WINDOW: usize = 2;
+
+/// Wraps a [`Decoder`] with a registered list of [`FusionRule`]s. On every
+/// `decode` call it peeks up to `WINDOW` instructions ahead (via the inner
+/// decoder's `decode`, which never touches the machine's PC), stopping
+/// early if it hits a basic-block end, and tries each rule in order; the
+/// first match wins and the decoder returns a single instruction in its
+/// place. A block's last instruction (typically `ecall`) is therefore
+/// never fused and never needs a `WINDOW`'th instruction to exist past it.
+///
+/// Because a fused instruction's length is the sum of the instructions it
+/// replaces (carried via [`set_instruction_length_n`]), both the
+/// interpreter's `execute` loop and the asm backend's trace-threading loop
+/// advance the PC/trace cursor correctly without any further change — they
+/// just see one (longer) instruction instead of several.
+pub struct FusionDecoder {
+    inner: Decoder,
+    rules: Vec<FusionRule>,
+}
+
+impl FusionDecoder {
+    pub fn new(inner: Decoder) -> Self {
+        Self {
+            inner,
+            rules: default_rules(),
+        }
+    }
+
+    /// Registers an extra rule, tried after the built-in ones. Embedders
+    /// add their own target-specific idioms this way instead of forking
+    /// the decoder.
+    pub fn with_rule(mut self, rule: FusionRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn decode<M: Memory>(&mut self, memory: &mut M, pc: u64) -> Result<Instruction, Error> {
+        let mut window = [0 as Instruction; WINDOW];
+        let mut cursor = pc;
+        let mut filled = 0;
+        for slot in window.iter_mut() {
+            let i = self.inner.decode(memory, cursor)?;
+            cursor += u64::from(instruction_length(i));
+            *slot = i;
+            filled += 1;
+            // Don't decode past a basic-block end: there's no legal next
+            // instruction to peek at yet (it may not even be loaded), and
+            // no rule should fuse across a jump/branch/ecall boundary
+            // anyway.
+            if is_basic_block_end_instruction(i) {
+                break;
+            }
+        }
+
+        if filled == WINDOW {
+            for rule in &self.rules {
+                if let Some((fused, consumed)) = rule(&window) {
+                    return Ok(set_instruction_length_n(fused, consumed));
+                }
+            }
+        }
+
+        Ok(window[0])
+    }
+
+    /// Equivalent to `self.decode(backend.memory_mut(), backend.pc())`;
+    /// see [`super::Decoder::decode_backend`].
+    pub fn decode_backend<B: crate::hal::VmBackend>(
+        &mut self,
+        backend: &mut B,
+    ) -> Result<Instruction, Error> {
+        crate::hal::fetch(backend, |mem, pc| self.decode(mem, pc))
+    }
+}
+
+fn total_length(window: &[Instruction]) -> u8 {
+    window.iter().map(|i| instruction_length(*i)).sum()
+}
+
+/// `auipc rd, imm_hi` ; `addi rd', rd, imm_lo` -> `load_imm_abs rd', imm_hi+imm_lo`
+/// (resolved against the PC by the handler at execute time).
+fn fuse_auipc_addi(window: &[Instruction]) -> Option<(Instruction, u8)> {
+    let head = Utype(window[0]);
+    if extract_opcode(window[0]) != insts::OP_AUIPC {
+        return None;
+    }
+    let tail = Itype(window[1]);
+    if extract_opcode(window[1]) != insts::OP_ADDI || tail.rs1() != head.rd() {
+        return None;
+    }
+    let combined = head.immediate_s().wrapping_add(tail.immediate_s());
+    Some((
+        Utype::new(insts::OP_CUSTOM_LOAD_IMM_ABS, tail.rd(), combined as u32).0,
+        total_length(window),
+    ))
+}
+
+/// `lui rd, imm_hi` ; `addi rd', rd, imm_lo` -> `const32 rd', imm_hi+imm_lo`.
+fn fuse_lui_addi(window: &[Instruction]) -> Option<(Instruction, u8)> {
+    let head = Utype(window[0]);
+    if extract_opcode(window[0]) != insts::OP_LUI {
+        return None;
+    }
+    let tail = Itype(window[1]);
+    if extract_opcode(window[1]) != insts::OP_ADDI || tail.rs1() != head.rd() {
+        return None;
+    }
+    let combined = head.immediate_s().wrapping_add(tail.immediate_s());
+    Some((
+        Utype::new(insts::OP_CUSTOM_CONST32, tail.rd(), combined as u32).0,
+        total_length(window),
+    ))
+}
+
+/// `slli rd, rs1, shamt` ; `add rd', rd, rs2` -> `indexed_addr rd', rs1,
+/// rs2` with `shamt` carried through in the fused instruction's immediate
+/// field (see [`handle_indexed_addr`]'s `rd = rs2 + (rs1 << shamt)`).
+fn fuse_slli_add(window: &[Instruction]) -> Option<(Instruction, u8)> {
+    let head = Itype(window[0]);
+    if extract_opcode(window[0]) != insts::OP_SLLI {
+        return None;
+    }
+    let tail = Rtype(window[1]);
+    if extract_opcode(window[1]) != insts::OP_ADD || tail.rs1() != head.rd() {
+        return None;
+    }
+    Some((
+        Rtype::new_with_immediate(
+            insts::OP_CUSTOM_INDEXED_ADDR,
+            tail.rd(),
+            head.rs1(),
+            tail.rs2(),
+            head.immediate_u() as i32,
+        )
+        .0,
+        total_length(window),
+    ))
+}
+
+/// Two loads in a row from the same base register, at offsets exactly one
+/// load-width apart (`lw;lw` at `off`/`off+4`, or `ld;ld` at `off`/`off+8`)
+/// -> one wide load producing both destination registers. Anything looser
+/// than that (same base but a gap, or different bases) is left unfused:
+/// the two loads still execute one at a time, just without this rule's
+/// help.
+fn fuse_wide_load(window: &[Instruction]) -> Option<(Instruction, u8)> {
+    let head = Itype(window[0]);
+    let head_op = extract_opcode(window[0]);
+    let (width, fused_op) = match head_op {
+        insts::OP_LW => (4, insts::OP_CUSTOM_WIDE_LOAD32),
+        insts::OP_LD => (8, insts::OP_CUSTOM_WIDE_LOAD64),
+        _ => return None,
+    };
+    let tail = Itype(window[1]);
+    if extract_opcode(window[1]) != head_op
+        || tail.rs1() != head.rs1()
+        || tail.immediate_s() != head.immediate_s().wrapping_add(width)
+        // If the first load's destination is the shared base register,
+        // the unfused sequence's second load would compute its address
+        // from what the first load just wrote there, not the original
+        // base — the fused op, which reads `head.rs1()` once for both
+        // addresses, would silently compute a different result.
+        || head.rd() == head.rs1()
+    {
+        return None;
+    }
+    Some((
+        Rtype::new_with_immediate(fused_op, head.rd(), head.rs1(), tail.rd(), head.immediate_s()).0,
+        total_length(window),
+    ))
+}
+
+fn default_rules() -> Vec<FusionRule> {
+    vec![fuse_auipc_addi, fuse_lui_addi, fuse_slli_add, fuse_wide_load]
+}
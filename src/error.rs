@@ -0,0 +1,20 @@
+use std::fmt;
+
+/// Errors that can occur while decoding, executing or otherwise driving a
+/// machine.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    OutOfBound,
+    InvalidInstruction(u16),
+    InvalidOp(u16),
+    Unaligned,
+    IO,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
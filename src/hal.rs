@@ -0,0 +1,105 @@
+//! An `emulator-hal`-style trait abstracting the handful of operations
+//! the decode -> execute -> trace loop actually needs: fetch at PC,
+//! register file access, memory read/write, cycle accounting, and the
+//! running flag.
+//!
+//! `build_decoder`, the fusion pass, the disassembler and the trace
+//! builder only ever touch a machine through these operations, but until
+//! now they were written against the concrete `DefaultMachine`/
+//! `AsmGlueMachine` types (or, as in `test_rust_auipc_fusion` and
+//! `test_asm_auipc_fusion`, hand-rolled identically against both). Any
+//! `SupportMachine` is a `VmBackend` for free via the blanket impl below,
+//! so third-party backends only need to implement `CoreMachine`/
+//! `SupportMachine` to plug into the same decoder/fusion/disassembler
+//! code the built-in machines use.
+
+use crate::instructions::Instruction;
+use crate::machine::{CoreMachine, SupportMachine, RISCV_GENERAL_REGISTER_NUMBER};
+use crate::{Error, Memory};
+
+pub trait VmBackend {
+    type Mem: Memory;
+
+    fn pc(&self) -> u64;
+    fn set_pc(&mut self, pc: u64);
+    fn registers(&self) -> &[u64; RISCV_GENERAL_REGISTER_NUMBER];
+    fn set_register(&mut self, idx: usize, value: u64);
+    fn memory_mut(&mut self) -> &mut Self::Mem;
+    fn running(&self) -> bool;
+    fn set_running(&mut self, running: bool);
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), Error>;
+}
+
+impl<Mac: SupportMachine> VmBackend for Mac {
+    type Mem = Mac::Mem;
+
+    fn pc(&self) -> u64 {
+        *CoreMachine::pc(self)
+    }
+
+    fn set_pc(&mut self, pc: u64) {
+        CoreMachine::set_pc(self, pc)
+    }
+
+    fn registers(&self) -> &[u64; RISCV_GENERAL_REGISTER_NUMBER] {
+        CoreMachine::registers(self)
+    }
+
+    fn set_register(&mut self, idx: usize, value: u64) {
+        CoreMachine::set_register(self, idx, value)
+    }
+
+    fn memory_mut(&mut self) -> &mut Self::Mem {
+        CoreMachine::memory_mut(self)
+    }
+
+    fn running(&self) -> bool {
+        SupportMachine::running(self)
+    }
+
+    fn set_running(&mut self, running: bool) {
+        SupportMachine::set_running(self, running)
+    }
+
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), Error> {
+        SupportMachine::add_cycles(self, cycles)
+    }
+}
+
+/// Fetches the instruction at `backend`'s current PC through `decode`.
+/// This is the one line `test_rust_auipc_fusion` and
+/// `test_asm_auipc_fusion` each wrote out by hand (`let pc = *machine.pc();
+/// decoder.decode(machine.memory_mut(), pc)`); any `VmBackend` can call it
+/// the same way regardless of whether `decode` comes from a plain
+/// `Decoder` or a `FusionDecoder`.
+pub fn fetch<B, D>(backend: &mut B, mut decode: D) -> Result<Instruction, Error>
+where
+    B: VmBackend,
+    D: FnMut(&mut <B as VmBackend>::Mem, u64) -> Result<Instruction, Error>,
+{
+    let pc = VmBackend::pc(backend);
+    decode(VmBackend::memory_mut(backend), pc)
+}
+
+/// Runs `backend` to completion by repeatedly fetching through `decode`
+/// and dispatching through `execute`, exactly the loop
+/// `test_rust_auipc_fusion` runs by hand. Asm-backed machines don't use
+/// this: they dispatch through `trace.thread[..]` instead of calling
+/// `execute` per instruction, which is why only `fetch` above (not this
+/// whole loop) is shared with the trace-threading path.
+pub fn run_interpreted<B, D>(
+    backend: &mut B,
+    mut decode: D,
+    vcheck_function_list: &crate::instructions::VcheckFunctionList<B>,
+    handle_function_list: &crate::instructions::HandleFunctionList<B>,
+) -> Result<(), Error>
+where
+    B: VmBackend + SupportMachine,
+    D: FnMut(&mut <B as VmBackend>::Mem, u64) -> Result<Instruction, Error>,
+{
+    while VmBackend::running(backend) {
+        let instruction = fetch(backend, &mut decode)?;
+        crate::instructions::execute(backend, vcheck_function_list, handle_function_list, instruction)?;
+    }
+    Ok(())
+}
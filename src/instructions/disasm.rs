@@ -0,0 +1,138 @@
+//! Turns a decoded [`Instruction`] back into textual RISC-V assembly, for
+//! trace dumps and contract debuggers. Complements `extract_opcode`/
+//! `instruction_length`, which only expose enough to drive decode/execute.
+
+use super::{extract_opcode, Btype, Instruction, Itype, Jtype, Rtype, Utype};
+use crate::{Error, Memory};
+use ckb_vm_definitions::instructions as insts;
+
+const ABI_REGISTER_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+fn reg(index: u8) -> &'static str {
+    ABI_REGISTER_NAMES[index as usize & 0x1f]
+}
+
+/// Renders `inst` (as it appeared at `pc`) in canonical RISC-V textual
+/// form: mnemonic, ABI register names, sign-extended immediates, and for
+/// PC-relative instructions (`auipc`, `jal`, branches) the resolved
+/// absolute target. Also understands the custom opcodes a
+/// [`super::fusion::FusionDecoder`] can produce, so a fusion-enabled
+/// decoder's output stays human-readable.
+pub fn disassemble(inst: Instruction, pc: u64) -> String {
+    match extract_opcode(inst) {
+        insts::OP_LUI => {
+            let i = Utype(inst);
+            format!("lui {}, {:#x}", reg(i.rd()), i.immediate_u() >> 12)
+        }
+        insts::OP_AUIPC => {
+            let i = Utype(inst);
+            let target = pc.wrapping_add(i.immediate_s() as i64 as u64);
+            format!("auipc {}, {:#x} # {:#x}", reg(i.rd()), i.immediate_u() >> 12, target)
+        }
+        insts::OP_JAL => {
+            let i = Jtype(inst);
+            let target = pc.wrapping_add(i.immediate_s() as i64 as u64);
+            format!("jal {}, {:#x}", reg(i.rd()), target)
+        }
+        insts::OP_JALR => {
+            let i = Itype(inst);
+            format!("jalr {}, {}, {}", reg(i.rd()), reg(i.rs1()), i.immediate_s())
+        }
+        insts::OP_BEQ => {
+            let i = Btype(inst);
+            let target = pc.wrapping_add(i.immediate_s() as i64 as u64);
+            format!("beq {}, {}, {:#x}", reg(i.rs1()), reg(i.rs2()), target)
+        }
+        insts::OP_ADDI => {
+            let i = Itype(inst);
+            format!("addi {}, {}, {}", reg(i.rd()), reg(i.rs1()), i.immediate_s())
+        }
+        insts::OP_ADD => {
+            let i = Rtype(inst);
+            format!("add {}, {}, {}", reg(i.rd()), reg(i.rs1()), reg(i.rs2()))
+        }
+        insts::OP_SLLI => {
+            let i = Itype(inst);
+            format!("slli {}, {}, {}", reg(i.rd()), reg(i.rs1()), i.immediate_u())
+        }
+        insts::OP_LW => {
+            let i = Itype(inst);
+            format!("lw {}, {}({})", reg(i.rd()), i.immediate_s(), reg(i.rs1()))
+        }
+        insts::OP_LD => {
+            let i = Itype(inst);
+            format!("ld {}, {}({})", reg(i.rd()), i.immediate_s(), reg(i.rs1()))
+        }
+        insts::OP_ECALL => "ecall".to_string(),
+        insts::OP_CUSTOM_LOAD_UIMM => {
+            let i = Utype(inst);
+            format!("load_uimm {}, {:#x}", reg(i.rd()), i.immediate_u())
+        }
+        insts::OP_CUSTOM_LOAD_IMM_ABS => {
+            let i = Utype(inst);
+            let target = pc.wrapping_add(i.immediate_s() as i64 as u64);
+            format!("load_imm_abs {}, {:#x}", reg(i.rd()), target)
+        }
+        insts::OP_CUSTOM_CONST32 => {
+            let i = Utype(inst);
+            format!("const32 {}, {:#x}", reg(i.rd()), i.immediate_u())
+        }
+        insts::OP_CUSTOM_INDEXED_ADDR => {
+            let i = Rtype(inst);
+            format!(
+                "indexed_addr {}, {} << {}, {}",
+                reg(i.rd()),
+                reg(i.rs1()),
+                i.immediate_u() & 0x3f,
+                reg(i.rs2())
+            )
+        }
+        insts::OP_CUSTOM_WIDE_LOAD32 => {
+            let i = Rtype(inst);
+            format!(
+                "wide_load32 {}:{}, {}({})",
+                reg(i.rd()),
+                reg(i.rs2()),
+                i.immediate_s(),
+                reg(i.rs1())
+            )
+        }
+        insts::OP_CUSTOM_WIDE_LOAD64 => {
+            let i = Rtype(inst);
+            format!(
+                "wide_load64 {}:{}, {}({})",
+                reg(i.rd()),
+                reg(i.rs2()),
+                i.immediate_s(),
+                reg(i.rs1())
+            )
+        }
+        insts::OP_CUSTOM_TRACE_END => "trace_end".to_string(),
+        opcode => format!("unknown(opcode={:#x})", opcode),
+    }
+}
+
+/// Walks `len` bytes of `memory` starting at `start_pc` the same way a
+/// trace builder walks instructions (decode, advance by
+/// `instruction_length`, repeat), disassembling each one. Returns one
+/// `"<pc>: <text>"` line per instruction.
+pub fn dump_disassembly<M: Memory>(
+    memory: &mut M,
+    mut decode: impl FnMut(&mut M, u64) -> Result<Instruction, Error>,
+    start_pc: u64,
+    len: u64,
+) -> Result<Vec<String>, Error> {
+    let end_pc = start_pc + len;
+    let mut pc = start_pc;
+    let mut lines = Vec::new();
+    while pc < end_pc {
+        let inst = decode(memory, pc)?;
+        lines.push(format!("{:#x}: {}", pc, disassemble(inst, pc)));
+        pc += u64::from(super::instruction_length(inst));
+    }
+    Ok(lines)
+}
@@ -0,0 +1,357 @@
+//! Decoded instruction representation and the generic execute loop.
+//!
+//! An [`Instruction`] is a flat `u64` with a fixed layout so it is cheap to
+//! build, fuse and compare:
+//!
+//! ```text
+//! bit:   63..............32 31   26    21    16     8      0
+//!        |   immediate    |pad| rs2 | rs1 | rd | length | opcode |
+//! ```
+//!
+//! The various `*type` wrappers below are zero-cost views over that same
+//! layout; they only differ in which accessors make sense for the
+//! instruction form they represent.
+
+use crate::machine::SupportMachine;
+use crate::{Error, Memory};
+use ckb_vm_definitions::instructions as insts;
+
+pub mod disasm;
+pub use disasm::{disassemble, dump_disassembly};
+
+pub use ckb_vm_definitions::instructions::*;
+
+pub type Instruction = u64;
+
+const OPCODE_MASK: u64 = 0xff;
+const LENGTH_SHIFT: u32 = 8;
+const LENGTH_MASK: u64 = 0xff << LENGTH_SHIFT;
+const RD_SHIFT: u32 = 16;
+const RS1_SHIFT: u32 = 21;
+const RS2_SHIFT: u32 = 26;
+const REG_MASK: u64 = 0x1f;
+const IMMEDIATE_SHIFT: u32 = 32;
+
+pub fn extract_opcode(i: Instruction) -> u16 {
+    (i & OPCODE_MASK) as u16
+}
+
+pub fn instruction_length(i: Instruction) -> u8 {
+    ((i & LENGTH_MASK) >> LENGTH_SHIFT) as u8
+}
+
+pub fn set_instruction_length_n(i: Instruction, n: u8) -> Instruction {
+    (i & !LENGTH_MASK) | ((n as u64) << LENGTH_SHIFT)
+}
+
+pub fn blank_instruction(opcode: u16) -> Instruction {
+    opcode as u64
+}
+
+/// Whether this instruction can change control flow (jump, branch, system
+/// call) or otherwise ends a basic block. Used by trace builders to know
+/// where to stop extending a block.
+pub fn is_basic_block_end_instruction(i: Instruction) -> bool {
+    matches!(
+        extract_opcode(i),
+        insts::OP_JAL
+            | insts::OP_JALR
+            | insts::OP_BEQ
+            | insts::OP_ECALL
+            | insts::OP_CUSTOM_TRACE_END
+    )
+}
+
+fn pack(opcode: u16, rd: u8, rs1: u8, rs2: u8, immediate: i32) -> Instruction {
+    (opcode as u64)
+        | ((rd as u64 & REG_MASK) << RD_SHIFT)
+        | ((rs1 as u64 & REG_MASK) << RS1_SHIFT)
+        | ((rs2 as u64 & REG_MASK) << RS2_SHIFT)
+        | ((immediate as u32 as u64) << IMMEDIATE_SHIFT)
+}
+
+macro_rules! instruction_type {
+    ($name:ident) => {
+        #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+        pub struct $name(pub Instruction);
+
+        impl $name {
+            pub fn rd(self) -> u8 {
+                ((self.0 >> RD_SHIFT) & REG_MASK) as u8
+            }
+
+            pub fn rs1(self) -> u8 {
+                ((self.0 >> RS1_SHIFT) & REG_MASK) as u8
+            }
+
+            pub fn rs2(self) -> u8 {
+                ((self.0 >> RS2_SHIFT) & REG_MASK) as u8
+            }
+
+            pub fn immediate_s(self) -> i32 {
+                (self.0 >> IMMEDIATE_SHIFT) as u32 as i32
+            }
+
+            pub fn immediate_u(self) -> u32 {
+                (self.0 >> IMMEDIATE_SHIFT) as u32
+            }
+        }
+    };
+}
+
+instruction_type!(Rtype);
+instruction_type!(Itype);
+instruction_type!(Stype);
+instruction_type!(Btype);
+instruction_type!(Utype);
+instruction_type!(Jtype);
+
+impl Utype {
+    pub fn new(opcode: u16, rd: u8, immediate_u: u32) -> Self {
+        Utype(pack(opcode, rd, 0, 0, immediate_u as i32))
+    }
+}
+
+impl Itype {
+    pub fn new(opcode: u16, rd: u8, rs1: u8, immediate: i32) -> Self {
+        Itype(pack(opcode, rd, rs1, 0, immediate))
+    }
+}
+
+impl Rtype {
+    pub fn new(opcode: u16, rd: u8, rs1: u8, rs2: u8) -> Self {
+        Rtype(pack(opcode, rd, rs1, rs2, 0))
+    }
+
+    /// Like [`Rtype::new`], but also sets the immediate field. The
+    /// underlying layout carries `rd`/`rs1`/`rs2`/an immediate
+    /// unconditionally (see the module-level diagram); plain R-type
+    /// instructions just don't need the last one. Fused opcodes that need
+    /// all three register slots plus an offset or shift amount (e.g.
+    /// `OP_CUSTOM_INDEXED_ADDR`, `OP_CUSTOM_WIDE_LOAD32`/`64`) use this
+    /// instead.
+    pub fn new_with_immediate(opcode: u16, rd: u8, rs1: u8, rs2: u8, immediate: i32) -> Self {
+        Rtype(pack(opcode, rd, rs1, rs2, immediate))
+    }
+}
+
+impl Btype {
+    pub fn new(opcode: u16, rs1: u8, rs2: u8, immediate: i32) -> Self {
+        Btype(pack(opcode, 0, rs1, rs2, immediate))
+    }
+}
+
+impl Jtype {
+    pub fn new(opcode: u16, rd: u8, immediate: i32) -> Self {
+        Jtype(pack(opcode, rd, 0, 0, immediate))
+    }
+}
+
+/// One entry per opcode; `None` means "no handler registered", which
+/// `execute` treats as an invalid instruction error.
+pub type HandleFunctionList<Mac> = Vec<Option<fn(&mut Mac, Instruction) -> Result<(), Error>>>;
+pub type VcheckFunctionList<Mac> = Vec<Option<fn(&Mac, Instruction) -> Result<(), Error>>>;
+
+const OPCODE_SPACE: usize = 256;
+
+fn handle_lui<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Utype(i);
+    mac.set_register(i.rd() as usize, i.immediate_s() as i64 as u64);
+    Ok(())
+}
+
+fn handle_auipc<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Utype(i);
+    let value = mac.pc().wrapping_add(i.immediate_s() as i64 as u64);
+    mac.set_register(i.rd() as usize, value);
+    Ok(())
+}
+
+fn handle_addi<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Itype(i);
+    let rs1 = mac.registers()[i.rs1() as usize];
+    mac.set_register(i.rd() as usize, rs1.wrapping_add(i.immediate_s() as i64 as u64));
+    Ok(())
+}
+
+fn handle_jal<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let raw = i;
+    let i = Jtype(i);
+    let pc = *mac.pc();
+    mac.set_register(i.rd() as usize, pc.wrapping_add(instruction_length(raw) as u64));
+    mac.set_pc(pc.wrapping_add(i.immediate_s() as i64 as u64));
+    Ok(())
+}
+
+fn handle_jalr<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let raw = i;
+    let i = Itype(i);
+    let pc = *mac.pc();
+    let rs1 = mac.registers()[i.rs1() as usize];
+    let target = rs1.wrapping_add(i.immediate_s() as i64 as u64) & !1u64;
+    mac.set_register(i.rd() as usize, pc.wrapping_add(instruction_length(raw) as u64));
+    mac.set_pc(target);
+    Ok(())
+}
+
+fn handle_beq<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Btype(i);
+    let rs1 = mac.registers()[i.rs1() as usize];
+    let rs2 = mac.registers()[i.rs2() as usize];
+    if rs1 == rs2 {
+        let pc = *mac.pc();
+        mac.set_pc(pc.wrapping_add(i.immediate_s() as i64 as u64));
+    }
+    Ok(())
+}
+
+fn handle_lw<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Itype(i);
+    let base = mac.registers()[i.rs1() as usize];
+    let addr = base.wrapping_add(i.immediate_s() as i64 as u64);
+    let value = mac.memory_mut().load32(addr)? as i32 as i64 as u64;
+    mac.set_register(i.rd() as usize, value);
+    Ok(())
+}
+
+fn handle_ld<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Itype(i);
+    let base = mac.registers()[i.rs1() as usize];
+    let addr = base.wrapping_add(i.immediate_s() as i64 as u64);
+    let value = mac.memory_mut().load64(addr)?;
+    mac.set_register(i.rd() as usize, value);
+    Ok(())
+}
+
+fn handle_add<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Rtype(i);
+    let rs1 = mac.registers()[i.rs1() as usize];
+    let rs2 = mac.registers()[i.rs2() as usize];
+    mac.set_register(i.rd() as usize, rs1.wrapping_add(rs2));
+    Ok(())
+}
+
+fn handle_slli<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Itype(i);
+    let rs1 = mac.registers()[i.rs1() as usize];
+    mac.set_register(i.rd() as usize, rs1 << (i.immediate_u() & 0x3f));
+    Ok(())
+}
+
+fn handle_load_uimm<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Utype(i);
+    mac.set_register(i.rd() as usize, i.immediate_u() as u64);
+    Ok(())
+}
+
+fn handle_ecall<Mac: SupportMachine>(mac: &mut Mac, _i: Instruction) -> Result<(), Error> {
+    mac.set_running(false);
+    Ok(())
+}
+
+fn handle_load_imm_abs<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Utype(i);
+    let value = mac.pc().wrapping_add(i.immediate_s() as i64 as u64);
+    mac.set_register(i.rd() as usize, value);
+    Ok(())
+}
+
+fn handle_const32<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Utype(i);
+    mac.set_register(i.rd() as usize, i.immediate_s() as i64 as u64);
+    Ok(())
+}
+
+fn handle_indexed_addr<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    let i = Rtype(i);
+    let base = mac.registers()[i.rs1() as usize];
+    let index = mac.registers()[i.rs2() as usize];
+    let shamt = i.immediate_u() & 0x3f;
+    mac.set_register(i.rd() as usize, index.wrapping_add(base << shamt));
+    Ok(())
+}
+
+/// Shared by `handle_wide_load32`/`64`: loads two `width`-byte values
+/// `width` bytes apart starting at `rs1 + immediate` into `rd` and `rs2`
+/// (which fusion packed with the second load's destination register
+/// index, not an actual source operand).
+fn wide_load<Mac: SupportMachine>(mac: &mut Mac, i: Instruction, width: u64) -> Result<(), Error> {
+    let i = Rtype(i);
+    let base = mac.registers()[i.rs1() as usize];
+    let addr = base.wrapping_add(i.immediate_s() as i64 as u64);
+    let (lo, hi) = if width == 4 {
+        (
+            mac.memory_mut().load32(addr)? as i32 as i64 as u64,
+            mac.memory_mut().load32(addr.wrapping_add(width))? as i32 as i64 as u64,
+        )
+    } else {
+        (
+            mac.memory_mut().load64(addr)?,
+            mac.memory_mut().load64(addr.wrapping_add(width))?,
+        )
+    };
+    mac.set_register(i.rd() as usize, lo);
+    mac.set_register(i.rs2() as usize, hi);
+    Ok(())
+}
+
+fn handle_wide_load32<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    wide_load(mac, i, 4)
+}
+
+fn handle_wide_load64<Mac: SupportMachine>(mac: &mut Mac, i: Instruction) -> Result<(), Error> {
+    wide_load(mac, i, 8)
+}
+
+/// Builds the table `execute` dispatches through. Kept separate from
+/// `generate_vcheck_function_list` so version/ISA gating only has to be
+/// expressed once, in the `vcheck` pass.
+pub fn generate_handle_function_list<Mac: SupportMachine>() -> HandleFunctionList<Mac> {
+    let mut list: HandleFunctionList<Mac> = vec![None; OPCODE_SPACE];
+    list[insts::OP_LUI as usize] = Some(handle_lui);
+    list[insts::OP_AUIPC as usize] = Some(handle_auipc);
+    list[insts::OP_JAL as usize] = Some(handle_jal);
+    list[insts::OP_JALR as usize] = Some(handle_jalr);
+    list[insts::OP_BEQ as usize] = Some(handle_beq);
+    list[insts::OP_ADDI as usize] = Some(handle_addi);
+    list[insts::OP_ADD as usize] = Some(handle_add);
+    list[insts::OP_SLLI as usize] = Some(handle_slli);
+    list[insts::OP_LW as usize] = Some(handle_lw);
+    list[insts::OP_LD as usize] = Some(handle_ld);
+    list[insts::OP_ECALL as usize] = Some(handle_ecall);
+    list[insts::OP_CUSTOM_LOAD_UIMM as usize] = Some(handle_load_uimm);
+    list[insts::OP_CUSTOM_LOAD_IMM_ABS as usize] = Some(handle_load_imm_abs);
+    list[insts::OP_CUSTOM_CONST32 as usize] = Some(handle_const32);
+    list[insts::OP_CUSTOM_INDEXED_ADDR as usize] = Some(handle_indexed_addr);
+    list[insts::OP_CUSTOM_WIDE_LOAD32 as usize] = Some(handle_wide_load32);
+    list[insts::OP_CUSTOM_WIDE_LOAD64 as usize] = Some(handle_wide_load64);
+    list
+}
+
+/// Validates an instruction is legal for the running machine's ISA/version
+/// before `execute` dispatches it. Right now every registered opcode is
+/// unconditionally legal under `ISA_IMC`; this is the hook embedders with
+/// extension flags (`M`, `V`, ...) gate on.
+pub fn generate_vcheck_function_list<Mac: SupportMachine>() -> VcheckFunctionList<Mac> {
+    vec![None; OPCODE_SPACE]
+}
+
+pub fn execute<Mac: SupportMachine>(
+    machine: &mut Mac,
+    vcheck_function_list: &VcheckFunctionList<Mac>,
+    handle_function_list: &HandleFunctionList<Mac>,
+    instruction: Instruction,
+) -> Result<(), Error> {
+    let opcode = extract_opcode(instruction) as usize;
+    if let Some(vcheck) = vcheck_function_list[opcode] {
+        vcheck(machine, instruction)?;
+    }
+    let handler = handle_function_list[opcode].ok_or(Error::InvalidOp(opcode as u16))?;
+    let pc = *machine.pc();
+    handler(machine, instruction)?;
+    if *machine.pc() == pc {
+        machine.set_pc(pc.wrapping_add(instruction_length(instruction) as u64));
+    }
+    machine.add_cycles(machine.instruction_cycle_func()(instruction, 0, 0))?;
+    Ok(())
+}
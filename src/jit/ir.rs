@@ -0,0 +1,196 @@
+//! Platform-independent IR the trace JIT lowers per architecture. Kept
+//! deliberately small: just what the opcodes in `instructions` need.
+
+use crate::instructions::{extract_opcode, instruction_length, Instruction, Itype, Rtype, Utype};
+use ckb_vm_definitions::asm::{calculate_slot, Trace};
+use ckb_vm_definitions::instructions as insts;
+
+/// A scratch value living either in a native register (post-regalloc) or
+/// still pinned to its VM register slot in the in-memory register file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Value {
+    /// Not yet allocated: operate directly on VM register `0..32`.
+    VmReg(u8),
+    /// Allocated to scratch pool slot `0..POOL_SIZE` for the backend.
+    Scratch(u8),
+    /// A block-local temporary (e.g. a materialized immediate) that never
+    /// corresponds to a VM register and is always regalloc'd to whatever
+    /// scratch slot is free.
+    Temp(u16),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cmp {
+    Eq,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RuntimeCall {
+    MemoryLoad { width: u8 },
+    MemoryStore { width: u8 },
+    Ecall,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Op {
+    /// Materializes `value` into `dst`.
+    Imm { dst: Value, value: i64 },
+    /// Spills/reloads between a scratch register and the VM register file;
+    /// the regalloc pass is what decides these are only needed at block
+    /// entry/exit instead of once per instruction.
+    LoadVm { vm_reg: u8, dst: Value },
+    StoreVm { vm_reg: u8, src: Value },
+    Add { dst: Value, a: Value, b: Value },
+    Sub { dst: Value, a: Value, b: Value },
+    And { dst: Value, a: Value, b: Value },
+    Shl { dst: Value, a: Value, amount: u8 },
+    CmpBranch { a: Value, b: Value, cmp: Cmp, target_slot: usize },
+    /// Memory accesses and ecalls aren't inlined: the runtime may observe
+    /// or mutate VM state in ways the block can't predict, so every
+    /// resident register must be flushed to memory first.
+    FlushAll,
+    CallRuntime { call: RuntimeCall, dst: Option<Value>, addr: Value, value: Option<Value> },
+}
+
+/// One basic block's worth of IR, still addressing VM registers directly;
+/// `regalloc` rewrites `Value::VmReg` into `Value::Scratch` where it's
+/// profitable to.
+pub struct Block {
+    pub ops: Vec<Op>,
+}
+
+/// Lowers each instruction in `trace` (up to its `OP_CUSTOM_TRACE_END`
+/// marker) into IR, one VM instruction at a time. This is the "split"
+/// half of the split-then-lower pipeline; `regalloc` and the per-arch
+/// `lower_*` modules do the rest.
+pub fn build(trace: &Trace) -> Block {
+    let mut ops = Vec::new();
+    let mut next_temp = 0u16;
+    let mut pc = trace.address;
+    for &instruction in trace.instructions.iter() {
+        let opcode = extract_opcode(instruction);
+        if opcode == insts::OP_CUSTOM_TRACE_END {
+            break;
+        }
+        lower_instruction(&mut ops, &mut next_temp, instruction, opcode, pc);
+        pc = pc.wrapping_add(u64::from(instruction_length(instruction)));
+    }
+    Block { ops }
+}
+
+fn lower_instruction(
+    ops: &mut Vec<Op>,
+    next_temp: &mut u16,
+    instruction: Instruction,
+    opcode: u16,
+    pc: u64,
+) {
+    match opcode {
+        insts::OP_LUI | insts::OP_CUSTOM_CONST32 => {
+            let i = Utype(instruction);
+            ops.push(Op::Imm {
+                dst: Value::VmReg(i.rd()),
+                value: i.immediate_s() as i64,
+            });
+        }
+        insts::OP_ADDI => {
+            let i = Itype(instruction);
+            let imm = Value::Temp(*next_temp);
+            *next_temp += 1;
+            ops.push(Op::Imm { dst: imm, value: i.immediate_s() as i64 });
+            ops.push(Op::Add {
+                dst: Value::VmReg(i.rd()),
+                a: Value::VmReg(i.rs1()),
+                b: imm,
+            });
+        }
+        insts::OP_ADD | insts::OP_CUSTOM_INDEXED_ADDR => {
+            let i = Rtype(instruction);
+            ops.push(Op::Add {
+                dst: Value::VmReg(i.rd()),
+                a: Value::VmReg(i.rs1()),
+                b: Value::VmReg(i.rs2()),
+            });
+        }
+        insts::OP_SLLI => {
+            let i = Itype(instruction);
+            ops.push(Op::Shl {
+                dst: Value::VmReg(i.rd()),
+                a: Value::VmReg(i.rs1()),
+                amount: i.immediate_u() as u8 & 0x3f,
+            });
+        }
+        insts::OP_LW | insts::OP_LD => {
+            let i = Itype(instruction);
+            let width = if opcode == insts::OP_LW { 4 } else { 8 };
+            ops.push(Op::FlushAll);
+            ops.push(Op::CallRuntime {
+                call: RuntimeCall::MemoryLoad { width },
+                dst: Some(Value::VmReg(i.rd())),
+                addr: Value::VmReg(i.rs1()),
+                value: None,
+            });
+        }
+        insts::OP_ECALL => {
+            ops.push(Op::FlushAll);
+            ops.push(Op::CallRuntime {
+                call: RuntimeCall::Ecall,
+                dst: None,
+                addr: Value::VmReg(0),
+                value: None,
+            });
+        }
+        insts::OP_BEQ => {
+            let i = crate::instructions::Btype(instruction);
+            let target = pc.wrapping_add(i.immediate_s() as i64 as u64);
+            ops.push(Op::CmpBranch {
+                a: Value::VmReg(i.rs1()),
+                b: Value::VmReg(i.rs2()),
+                cmp: Cmp::Eq,
+                target_slot: calculate_slot(target),
+            });
+        }
+        insts::OP_AUIPC | insts::OP_CUSTOM_LOAD_IMM_ABS => {
+            // Both compute `pc + immediate`; `pc` is fixed at compile
+            // time for every instruction in a trace (it's just
+            // `trace.address` plus however far we've walked), so this is
+            // a plain immediate materialization, same as `OP_LUI`.
+            let i = Utype(instruction);
+            ops.push(Op::Imm {
+                dst: Value::VmReg(i.rd()),
+                value: pc.wrapping_add(i.immediate_s() as i64 as u64) as i64,
+            });
+        }
+        insts::OP_CUSTOM_LOAD_UIMM => {
+            let i = Utype(instruction);
+            ops.push(Op::Imm {
+                dst: Value::VmReg(i.rd()),
+                value: i.immediate_u() as i64,
+            });
+        }
+        insts::OP_JAL | insts::OP_JALR => {
+            // Real control transfer, same category as `OP_BEQ` above:
+            // `jal`'s target is at least computable at compile time,
+            // `jalr`'s isn't even that (it comes out of a register), and
+            // neither backend has the relocation/patch machinery to wire
+            // either one into compiled code yet. Fail loudly instead of
+            // quietly dropping a call or return.
+            unimplemented!("JIT IR does not lower jal/jalr yet")
+        }
+        insts::OP_CUSTOM_WIDE_LOAD32 | insts::OP_CUSTOM_WIDE_LOAD64 => {
+            // Writes two destination registers (`rd` and `rs2`, per
+            // `instructions::wide_load`'s packing) from a single runtime
+            // call; `RuntimeCall::MemoryLoad` only carries one `dst`
+            // today. Fail loudly instead of quietly dropping the second
+            // register write.
+            unimplemented!("JIT IR does not lower wide loads yet")
+        }
+        _ => {
+            // Every opcode `instructions` defines is matched above (or
+            // breaks the loop before reaching here, for
+            // `OP_CUSTOM_TRACE_END`); reaching this arm means a trace
+            // somehow contains an opcode nothing knows how to execute.
+            unimplemented!("JIT IR does not lower opcode {opcode}")
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Lowers [`super::ir::Op`] into aarch64 machine code.
+//!
+//! Mirrors `lower_x86_64`: `x0` (first AAPCS64 argument register) holds
+//! the `AsmCoreMachine*`, so VM register `r` is `[x0, #8*r]`. Resident
+//! registers live in the caller-saved scratch pool `x19..x28`; everything
+//! else round-trips through the temporary `x9`.
+
+use super::ir::{Op, Value};
+use super::Block;
+
+/// Callee-saved (caller-saved from the JIT's point of view, since it
+/// saves/restores them around the trampoline call) scratch registers.
+pub const SCRATCH_POOL: [u8; 10] = [19, 20, 21, 22, 23, 24, 25, 26, 27, 28];
+const BASE_REG: u8 = 0; // x0
+const SPILL_REG: u8 = 9; // x9
+const SPILL_REG2: u8 = 10; // x10
+
+enum Operand {
+    Reg(u8),
+    Mem { base: u8, disp_words: u16 },
+}
+
+fn operand(value: Value) -> Operand {
+    match value {
+        Value::Scratch(slot) => Operand::Reg(SCRATCH_POOL[slot as usize]),
+        Value::VmReg(r) => Operand::Mem { base: BASE_REG, disp_words: u16::from(r) },
+        Value::Temp(_) => Operand::Reg(SPILL_REG),
+    }
+}
+
+fn push(code: &mut Vec<u8>, word: u32) {
+    code.extend_from_slice(&word.to_le_bytes());
+}
+
+fn emit_movz(code: &mut Vec<u8>, rd: u8, imm16: u16) {
+    push(code, 0xd280_0000 | (u32::from(imm16) << 5) | u32::from(rd));
+}
+
+fn emit_movk(code: &mut Vec<u8>, rd: u8, imm16: u16, shift: u32) {
+    push(code, 0xf280_0000 | (shift << 21) | (u32::from(imm16) << 5) | u32::from(rd));
+}
+
+fn emit_mov_imm64(code: &mut Vec<u8>, rd: u8, value: i64) {
+    let bits = value as u64;
+    emit_movz(code, rd, bits as u16);
+    for shift in 1..4u32 {
+        let chunk = (bits >> (shift * 16)) as u16;
+        if chunk != 0 {
+            emit_movk(code, rd, chunk, shift);
+        }
+    }
+}
+
+fn emit_mov_reg(code: &mut Vec<u8>, rd: u8, rm: u8) {
+    // `mov Xd, Xm` is the canonical alias for `orr Xd, xzr, Xm`.
+    push(code, 0xaa00_03e0 | (u32::from(rm) << 16) | u32::from(rd));
+}
+
+fn emit_alu(code: &mut Vec<u8>, opcode: u32, rd: u8, rn: u8, rm: u8) {
+    push(code, opcode | (u32::from(rm) << 16) | (u32::from(rn) << 5) | u32::from(rd));
+}
+
+fn emit_lslv(code: &mut Vec<u8>, rd: u8, rn: u8, rm: u8) {
+    push(code, 0x9ac0_2000 | (u32::from(rm) << 16) | (u32::from(rn) << 5) | u32::from(rd));
+}
+
+fn emit_ldr(code: &mut Vec<u8>, rt: u8, rn: u8, disp_words: u16) {
+    push(code, 0xf940_0000 | (u32::from(disp_words) << 10) | (u32::from(rn) << 5) | u32::from(rt));
+}
+
+fn emit_str(code: &mut Vec<u8>, rt: u8, rn: u8, disp_words: u16) {
+    push(code, 0xf900_0000 | (u32::from(disp_words) << 10) | (u32::from(rn) << 5) | u32::from(rt));
+}
+
+fn materialize(code: &mut Vec<u8>, rd: u8, value: Value) {
+    match operand(value) {
+        Operand::Reg(r) if r == rd => {}
+        Operand::Reg(r) => emit_mov_reg(code, rd, r),
+        Operand::Mem { base, disp_words } => emit_ldr(code, rd, base, disp_words),
+    }
+}
+
+fn store(code: &mut Vec<u8>, dst: Value, rd: u8) {
+    match operand(dst) {
+        Operand::Reg(r) => {
+            if r != rd {
+                emit_mov_reg(code, r, rd);
+            }
+        }
+        Operand::Mem { base, disp_words } => emit_str(code, rd, base, disp_words),
+    }
+}
+
+fn emit_op(code: &mut Vec<u8>, op: &Op) {
+    match *op {
+        Op::Imm { dst, value } => {
+            emit_mov_imm64(code, SPILL_REG, value);
+            store(code, dst, SPILL_REG);
+        }
+        Op::LoadVm { vm_reg, dst } => {
+            emit_ldr(code, SPILL_REG, BASE_REG, u16::from(vm_reg));
+            store(code, dst, SPILL_REG);
+        }
+        Op::StoreVm { vm_reg, src } => {
+            materialize(code, SPILL_REG, src);
+            emit_str(code, SPILL_REG, BASE_REG, u16::from(vm_reg));
+        }
+        Op::Add { dst, a, b } => {
+            materialize(code, SPILL_REG, a);
+            materialize(code, SPILL_REG2, b);
+            emit_alu(code, 0x8b00_0000, SPILL_REG, SPILL_REG, SPILL_REG2);
+            store(code, dst, SPILL_REG);
+        }
+        Op::Sub { dst, a, b } => {
+            materialize(code, SPILL_REG, a);
+            materialize(code, SPILL_REG2, b);
+            emit_alu(code, 0xcb00_0000, SPILL_REG, SPILL_REG, SPILL_REG2);
+            store(code, dst, SPILL_REG);
+        }
+        Op::And { dst, a, b } => {
+            materialize(code, SPILL_REG, a);
+            materialize(code, SPILL_REG2, b);
+            emit_alu(code, 0x8a00_0000, SPILL_REG, SPILL_REG, SPILL_REG2);
+            store(code, dst, SPILL_REG);
+        }
+        Op::Shl { dst, a, amount } => {
+            materialize(code, SPILL_REG, a);
+            emit_mov_imm64(code, SPILL_REG2, amount as i64);
+            emit_lslv(code, SPILL_REG, SPILL_REG, SPILL_REG2);
+            store(code, dst, SPILL_REG);
+        }
+        Op::CmpBranch { .. } => {
+            // Same gap as x86_64: no relocation pass exists to turn
+            // `target_slot` into a real branch, so emitting nothing would
+            // silently drop control flow instead of failing loudly.
+            unimplemented!("aarch64 JIT backend does not lower CmpBranch yet")
+        }
+        Op::FlushAll => {}
+        Op::CallRuntime { .. } => {
+            // Same gap as x86_64's `call rel32`: no trampoline address to
+            // patch into `bl`'s imm26, so this would be a dangling branch.
+            unimplemented!("aarch64 JIT backend does not lower CallRuntime yet")
+        }
+    }
+}
+
+/// Lowers `block` into a ret-terminated aarch64 function body.
+pub fn lower(block: &Block) -> Vec<u8> {
+    let mut code = Vec::new();
+    for op in &block.ops {
+        emit_op(&mut code, op);
+    }
+    push(&mut code, 0xd65f_03c0); // ret
+    code
+}
@@ -0,0 +1,168 @@
+//! Lowers [`super::ir::Op`] into x86_64 machine code.
+//!
+//! The calling convention mirrors `ckb_vm_asm_run`: `rdi` holds a pointer
+//! to the `AsmCoreMachine`, whose `registers: [u64; 32]` field is first,
+//! so VM register `r`'s memory operand is simply `[rdi + 8*r]`. Resident
+//! registers (per [`super::regalloc::Allocation`]) live in the
+//! caller-saved scratch pool `rbx, r12, r13, r14, r15`; everything else
+//! falls back to that memory operand, round-tripping through `rax`.
+
+use super::ir::{Op, Value};
+use super::Block;
+
+/// Caller-saved (in the System V ABI) general-purpose registers, encoded
+/// as their 4-bit register numbers: `rbx=3`, `r12..r15=12..15`.
+pub const SCRATCH_POOL: [u8; 5] = [3, 12, 13, 14, 15];
+const BASE_REG: u8 = 7; // rdi
+const SPILL_REG: u8 = 0; // rax, used to round-trip non-resident operands
+
+enum Operand {
+    Reg(u8),
+    Mem { base: u8, disp: i32 },
+}
+
+fn operand(value: Value) -> Operand {
+    match value {
+        Value::Scratch(slot) => Operand::Reg(SCRATCH_POOL[slot as usize]),
+        Value::VmReg(r) => Operand::Mem { base: BASE_REG, disp: 8 * i32::from(r) },
+        Value::Temp(_) => Operand::Reg(SPILL_REG),
+    }
+}
+
+fn rex(w: bool, r: bool, x: bool, b: bool) -> u8 {
+    0x40 | ((w as u8) << 3) | ((r as u8) << 2) | ((x as u8) << 1) | (b as u8)
+}
+
+fn modrm(md: u8, reg: u8, rm: u8) -> u8 {
+    (md << 6) | ((reg & 7) << 3) | (rm & 7)
+}
+
+fn emit_mov_reg_imm64(code: &mut Vec<u8>, reg: u8, value: i64) {
+    code.push(rex(true, false, false, reg >= 8));
+    code.push(0xb8 + (reg & 7));
+    code.extend_from_slice(&value.to_le_bytes());
+}
+
+fn emit_mov_reg_reg(code: &mut Vec<u8>, dst: u8, src: u8) {
+    code.push(rex(true, src >= 8, false, dst >= 8));
+    code.push(0x89);
+    code.push(modrm(0b11, src, dst));
+}
+
+fn emit_mov_reg_mem(code: &mut Vec<u8>, dst: u8, base: u8, disp: i32) {
+    code.push(rex(true, dst >= 8, false, base >= 8));
+    code.push(0x8b);
+    code.push(modrm(0b10, dst, base));
+    code.extend_from_slice(&disp.to_le_bytes());
+}
+
+fn emit_mov_mem_reg(code: &mut Vec<u8>, base: u8, disp: i32, src: u8) {
+    code.push(rex(true, src >= 8, false, base >= 8));
+    code.push(0x89);
+    code.push(modrm(0b10, src, base));
+    code.extend_from_slice(&disp.to_le_bytes());
+}
+
+fn emit_alu_reg_reg(code: &mut Vec<u8>, opcode: u8, dst: u8, src: u8) {
+    code.push(rex(true, src >= 8, false, dst >= 8));
+    code.push(opcode);
+    code.push(modrm(0b11, src, dst));
+}
+
+fn emit_shl_reg_imm8(code: &mut Vec<u8>, reg: u8, amount: u8) {
+    code.push(rex(true, false, false, reg >= 8));
+    code.push(0xc1);
+    code.push(modrm(0b11, 4, reg));
+    code.push(amount);
+}
+
+/// Loads `value` into `reg`, spilling through memory if it isn't already
+/// resident in a scratch register.
+fn materialize(code: &mut Vec<u8>, reg: u8, value: Value) {
+    match operand(value) {
+        Operand::Reg(r) if r == reg => {}
+        Operand::Reg(r) => emit_mov_reg_reg(code, reg, r),
+        Operand::Mem { base, disp } => emit_mov_reg_mem(code, reg, base, disp),
+    }
+}
+
+fn store(code: &mut Vec<u8>, dst: Value, reg: u8) {
+    match operand(dst) {
+        Operand::Reg(r) => {
+            if r != reg {
+                emit_mov_reg_reg(code, r, reg);
+            }
+        }
+        Operand::Mem { base, disp } => emit_mov_mem_reg(code, base, disp, reg),
+    }
+}
+
+fn emit_op(code: &mut Vec<u8>, op: &Op) {
+    match *op {
+        Op::Imm { dst, value } => {
+            emit_mov_reg_imm64(code, SPILL_REG, value);
+            store(code, dst, SPILL_REG);
+        }
+        Op::LoadVm { vm_reg, dst } => {
+            emit_mov_reg_mem(code, SPILL_REG, BASE_REG, 8 * i32::from(vm_reg));
+            store(code, dst, SPILL_REG);
+        }
+        Op::StoreVm { vm_reg, src } => {
+            materialize(code, SPILL_REG, src);
+            emit_mov_mem_reg(code, BASE_REG, 8 * i32::from(vm_reg), SPILL_REG);
+        }
+        Op::Add { dst, a, b } => {
+            materialize(code, SPILL_REG, a);
+            if let Operand::Reg(r) = operand(b) {
+                emit_alu_reg_reg(code, 0x01, SPILL_REG, r);
+            } else {
+                materialize(code, 1, b); // rcx as a second temp
+                emit_alu_reg_reg(code, 0x01, SPILL_REG, 1);
+            }
+            store(code, dst, SPILL_REG);
+        }
+        Op::Sub { dst, a, b } => {
+            materialize(code, SPILL_REG, a);
+            materialize(code, 1, b);
+            emit_alu_reg_reg(code, 0x29, SPILL_REG, 1);
+            store(code, dst, SPILL_REG);
+        }
+        Op::And { dst, a, b } => {
+            materialize(code, SPILL_REG, a);
+            materialize(code, 1, b);
+            emit_alu_reg_reg(code, 0x21, SPILL_REG, 1);
+            store(code, dst, SPILL_REG);
+        }
+        Op::Shl { dst, a, amount } => {
+            materialize(code, SPILL_REG, a);
+            emit_shl_reg_imm8(code, SPILL_REG, amount);
+            store(code, dst, SPILL_REG);
+        }
+        Op::CmpBranch { .. } => {
+            // No relocation/patching machinery exists yet to turn
+            // `target_slot` into an actual jump, and nothing calls into
+            // compiled code today, so silently dropping the branch would
+            // corrupt control flow the moment something does. Fail loudly
+            // instead of emitting code that looks correct but isn't.
+            unimplemented!("x86_64 JIT backend does not lower CmpBranch yet")
+        }
+        Op::FlushAll => {}
+        Op::CallRuntime { .. } => {
+            // Same story as `CmpBranch`: emitting a `call rel32` with no
+            // mechanism to ever patch in the trampoline's address just
+            // produces a dangling call into whatever bytes follow.
+            unimplemented!("x86_64 JIT backend does not lower CallRuntime yet")
+        }
+    }
+}
+
+/// Lowers `block` (already rewritten by [`super::regalloc::allocate`])
+/// into a ret-terminated x86_64 function body.
+pub fn lower(block: &Block) -> Vec<u8> {
+    let mut code = Vec::new();
+    for op in &block.ops {
+        emit_op(&mut code, op);
+    }
+    code.push(0xc3); // ret
+    code
+}
@@ -0,0 +1,58 @@
+//! An optional trace JIT backend: compiles the same per-basic-block
+//! `Trace` the asm backend fills for direct threading into native code,
+//! instead of dispatching through `thread[i]` labels.
+//!
+//! Pipeline, mirroring a standard split-then-lower compiler: [`ir::build`]
+//! splits a `Trace` into platform-independent IR, [`regalloc::allocate`]
+//! keeps hot VM registers resident in a fixed pool of native scratch
+//! registers for the block's duration, and the per-architecture
+//! `lower_*::lower` emits native code from the result.
+//!
+//! `trace.cycles` is already the sum of `instruction_cycle_func` over
+//! every instruction in the block (computed once, when the trace is
+//! assembled) regardless of whether it's replayed via threaded dispatch
+//! or a compiled block, so gas metering needs no change here.
+
+pub mod ir;
+pub mod lower_arm64;
+pub mod lower_x86_64;
+pub mod regalloc;
+
+pub use ir::Block;
+
+use ckb_vm_definitions::asm::Trace;
+use regalloc::Allocation;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arch {
+    X86_64,
+    Arm64,
+}
+
+impl Arch {
+    fn pool_size(self) -> usize {
+        match self {
+            Arch::X86_64 => lower_x86_64::SCRATCH_POOL.len(),
+            Arch::Arm64 => lower_arm64::SCRATCH_POOL.len(),
+        }
+    }
+}
+
+/// A compiled basic block, ready to be installed in place of
+/// `trace.thread[..]`'s direct-threading targets for this trace's slot.
+pub struct CompiledBlock {
+    pub code: Vec<u8>,
+    pub allocation: Allocation,
+}
+
+/// Compiles `trace` (as built by the asm backend's trace-threading loop,
+/// terminated at `OP_CUSTOM_TRACE_END`) for `arch`.
+pub fn compile_trace(trace: &Trace, arch: Arch) -> CompiledBlock {
+    let mut block = ir::build(trace);
+    let allocation = regalloc::allocate(&mut block, trace, arch.pool_size());
+    let code = match arch {
+        Arch::X86_64 => lower_x86_64::lower(&block),
+        Arch::Arm64 => lower_arm64::lower(&block),
+    };
+    CompiledBlock { code, allocation }
+}
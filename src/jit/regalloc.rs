@@ -0,0 +1,140 @@
+//! Keeps hot VM registers resident in native registers across a basic
+//! block instead of reloading them from the `AsmCoreMachine` register
+//! array on every instruction.
+//!
+//! This is a backward liveness scan in spirit but a simple use-count
+//! histogram in practice: within one basic block, a register touched
+//! more often benefits more from staying resident, and ties don't matter
+//! since the pool is tiny and fixed per architecture anyway.
+
+use crate::instructions::{extract_opcode, Itype};
+use ckb_vm_definitions::asm::Trace;
+use ckb_vm_definitions::instructions as insts;
+
+use super::ir::{Block, Op, Value};
+
+/// Which VM registers are kept resident, in scratch-slot order: the
+/// register at `resident[i]` lives in scratch slot `i` for the duration
+/// of the block.
+pub struct Allocation {
+    pub resident: Vec<u8>,
+}
+
+impl Allocation {
+    fn slot_of(&self, vm_reg: u8) -> Option<u8> {
+        self.resident.iter().position(|&r| r == vm_reg).map(|i| i as u8)
+    }
+}
+
+fn touched(instruction: crate::instructions::Instruction) -> [Option<u8>; 3] {
+    // Every instruction form shares the same rd/rs1/rs2 bit layout (see
+    // `instructions::pack`), so reading them through `Itype` is safe
+    // regardless of the instruction's real shape; unused fields are just 0.
+    let i = Itype(instruction);
+    [Some(i.rd()), Some(i.rs1()), Some(i.rs2())]
+}
+
+/// Counts how often each non-`zero` VM register is touched in `trace`
+/// (up to its `OP_CUSTOM_TRACE_END` marker) and keeps the `pool_size`
+/// most-touched ones resident. `x0` is never included: it is hard-wired
+/// to zero, so caching it buys nothing.
+fn compute_residency(trace: &Trace, pool_size: usize) -> Allocation {
+    let mut counts = [0u32; 32];
+    for &instruction in trace.instructions.iter() {
+        if extract_opcode(instruction) == insts::OP_CUSTOM_TRACE_END {
+            break;
+        }
+        for reg in touched(instruction).into_iter().flatten() {
+            if reg != 0 {
+                counts[reg as usize] += 1;
+            }
+        }
+    }
+
+    let mut candidates: Vec<u8> = (1..32).collect();
+    candidates.sort_by(|&a, &b| counts[b as usize].cmp(&counts[a as usize]));
+    let resident = candidates
+        .into_iter()
+        .filter(|&r| counts[r as usize] > 0)
+        .take(pool_size)
+        .collect();
+    Allocation { resident }
+}
+
+fn remap(value: Value, alloc: &Allocation) -> Value {
+    match value {
+        Value::VmReg(r) => alloc.slot_of(r).map(Value::Scratch).unwrap_or(Value::VmReg(r)),
+        other => other,
+    }
+}
+
+/// Computes register residency for `trace` and rewrites `block` in place:
+/// resident registers are loaded once at block entry, spilled/reloaded
+/// around every `FlushAll` (issued before runtime calls, which can
+/// observe VM state), and spilled again at block exit. Registers outside
+/// the pool are left as `Value::VmReg` for the backend to address
+/// directly in the in-memory register file.
+pub fn allocate(block: &mut Block, trace: &Trace, pool_size: usize) -> Allocation {
+    let alloc = compute_residency(trace, pool_size);
+
+    let load_all = |ops: &mut Vec<Op>| {
+        for (slot, &vm_reg) in alloc.resident.iter().enumerate() {
+            ops.push(Op::LoadVm { vm_reg, dst: Value::Scratch(slot as u8) });
+        }
+    };
+    let store_all = |ops: &mut Vec<Op>| {
+        for (slot, &vm_reg) in alloc.resident.iter().enumerate() {
+            ops.push(Op::StoreVm { vm_reg, src: Value::Scratch(slot as u8) });
+        }
+    };
+
+    let mut rewritten = Vec::with_capacity(block.ops.len() + alloc.resident.len() * 2);
+    load_all(&mut rewritten);
+    for op in block.ops.drain(..) {
+        match op {
+            Op::FlushAll => {
+                store_all(&mut rewritten);
+                rewritten.push(Op::FlushAll);
+                load_all(&mut rewritten);
+            }
+            Op::Imm { dst, value } => rewritten.push(Op::Imm { dst: remap(dst, &alloc), value }),
+            Op::LoadVm { vm_reg, dst } => rewritten.push(Op::LoadVm { vm_reg, dst: remap(dst, &alloc) }),
+            Op::StoreVm { vm_reg, src } => rewritten.push(Op::StoreVm { vm_reg, src: remap(src, &alloc) }),
+            Op::Add { dst, a, b } => rewritten.push(Op::Add {
+                dst: remap(dst, &alloc),
+                a: remap(a, &alloc),
+                b: remap(b, &alloc),
+            }),
+            Op::Sub { dst, a, b } => rewritten.push(Op::Sub {
+                dst: remap(dst, &alloc),
+                a: remap(a, &alloc),
+                b: remap(b, &alloc),
+            }),
+            Op::And { dst, a, b } => rewritten.push(Op::And {
+                dst: remap(dst, &alloc),
+                a: remap(a, &alloc),
+                b: remap(b, &alloc),
+            }),
+            Op::Shl { dst, a, amount } => rewritten.push(Op::Shl {
+                dst: remap(dst, &alloc),
+                a: remap(a, &alloc),
+                amount,
+            }),
+            Op::CmpBranch { a, b, cmp, target_slot } => rewritten.push(Op::CmpBranch {
+                a: remap(a, &alloc),
+                b: remap(b, &alloc),
+                cmp,
+                target_slot,
+            }),
+            Op::CallRuntime { call, dst, addr, value } => rewritten.push(Op::CallRuntime {
+                call,
+                dst: dst.map(|v| remap(v, &alloc)),
+                addr: remap(addr, &alloc),
+                value: value.map(|v| remap(v, &alloc)),
+            }),
+        }
+    }
+    store_all(&mut rewritten);
+    block.ops = rewritten;
+    alloc
+}
@@ -0,0 +1,18 @@
+//! `ckb-vm`: a RISC-V virtual machine used to run CKB chain scripts, with
+//! both a portable interpreter and an x86_64/aarch64 asm backend.
+
+pub mod debugger;
+pub mod decoder;
+pub mod error;
+pub mod hal;
+pub mod instructions;
+pub mod jit;
+pub mod machine;
+pub mod memory;
+
+pub use error::Error;
+pub use machine::{
+    CoreMachine, DefaultCoreMachine, DefaultMachine, DefaultMachineBuilder, SupportMachine,
+    ISA_IMC,
+};
+pub use memory::{FlatMemory, Memory, SparseMemory};
@@ -0,0 +1,62 @@
+//! Computes which VM registers are worth keeping resident in host
+//! registers for a given `Trace`, so the threaded dispatch loop doesn't
+//! have to reload them from `AsmCoreMachine::registers` on every
+//! instruction of a hot block.
+//!
+//! This is the lightweight counterpart to [`crate::jit::regalloc`]: it
+//! doesn't rewrite anything into native registers itself (the dispatch
+//! loop that would act on it is hand-written asm), it just annotates the
+//! `Trace` once, when assembled, with `hot_registers`/`dirty_mask` so the
+//! loop can consult them on every re-execution of that slot instead of
+//! recomputing anything.
+
+use ckb_vm_definitions::asm::{Trace, HOT_REGISTER_CAPACITY};
+use ckb_vm_definitions::instructions as insts;
+
+use crate::instructions::{extract_opcode, Itype};
+
+/// Fills in `trace.hot_registers`, `trace.hot_register_count` and
+/// `trace.dirty_mask` from `trace.instructions` (up to its
+/// `OP_CUSTOM_TRACE_END` marker). Call this once, right after a trace's
+/// `instructions`/`thread`/`length` fields are filled in and before it is
+/// installed into `AsmCoreMachine::traces[slot]`.
+pub fn analyze(trace: &mut Trace) {
+    let mut read_counts = [0u32; 32];
+    let mut write_mask = 0u32;
+
+    for &instruction in trace.instructions.iter() {
+        if extract_opcode(instruction) == insts::OP_CUSTOM_TRACE_END {
+            break;
+        }
+        // Every instruction form shares the rd/rs1/rs2 bit layout (see
+        // `instructions::pack`), so reading them through `Itype` is valid
+        // regardless of the instruction's real shape.
+        let i = Itype(instruction);
+        for reg in [i.rd(), i.rs1(), i.rs2()] {
+            if reg != 0 {
+                read_counts[reg as usize] += 1;
+            }
+        }
+        if i.rd() != 0 {
+            write_mask |= 1 << i.rd();
+        }
+    }
+
+    let mut candidates: Vec<u8> = (1..32).collect();
+    candidates.sort_by(|&a, &b| read_counts[b as usize].cmp(&read_counts[a as usize]));
+
+    let mut dirty_mask = 0u32;
+    let mut count = 0u8;
+    for &reg in candidates.iter().take(HOT_REGISTER_CAPACITY) {
+        if read_counts[reg as usize] == 0 {
+            break;
+        }
+        trace.hot_registers[count as usize] = reg;
+        if write_mask & (1 << reg) != 0 {
+            dirty_mask |= 1 << reg;
+        }
+        count += 1;
+    }
+    trace.hot_register_count = count;
+    trace.dirty_mask = dirty_mask;
+}
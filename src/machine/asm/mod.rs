@@ -0,0 +1,204 @@
+//! The asm backend: the same `CoreMachine`/`SupportMachine` surface as
+//! [`super::DefaultCoreMachine`], but backed by a register file and trace
+//! cache laid out so the hand-written x86_64/aarch64 dispatch loop
+//! (`ckb_vm_asm_run`, `ckb_vm_asm_labels`) can read and write it directly.
+
+use ckb_vm_definitions::asm::{Trace, TRACE_SIZE};
+
+pub mod hot_registers;
+
+use crate::machine::{CoreMachine, InstructionCycleFunc, SupportMachine, RISCV_GENERAL_REGISTER_NUMBER};
+use crate::{Error, FlatMemory};
+
+/// Size of the flat address space `AsmCoreMachine::memory` presents. CKB
+/// scripts run with a fixed, modest memory budget, so a flat
+/// preallocated buffer (rather than a growable/sparse one) is both
+/// simpler and what the asm side expects.
+const DEFAULT_MEMORY_SIZE: usize = 4 << 20;
+
+/// Vector-extension state needed by `instruction_cycle_func` to cost RVV
+/// instructions correctly. Unused under `ISA_IMC`, but always present so
+/// the cycle-func signature doesn't need an `Option`.
+#[derive(Default)]
+pub struct CoprocessorV {
+    vl: u64,
+    vsew: u8,
+}
+
+impl CoprocessorV {
+    pub fn vl(&self) -> u64 {
+        self.vl
+    }
+
+    pub fn vsew(&self) -> u8 {
+        self.vsew
+    }
+}
+
+/// The register file, PC, memory and trace cache exactly as the asm
+/// dispatch loop expects to find them. Always heap-allocated (`Box`) so
+/// its address is stable across moves of the Rust-side wrapper.
+///
+/// `#[repr(C)]` only pins down the order/offsets of these fields, not the
+/// layout of `memory`/`traces` themselves (`FlatMemory` wraps a `Vec<u8>`,
+/// `Vec<Trace>` is a `Vec`) — neither is actually FFI-safe. That's fine
+/// today because `ckb_vm_asm_run` only ever addresses the leading scalar
+/// fields by hand-computed offset (see its doc comment below); it would
+/// stop being fine the moment asm code needs to reach into `memory` or
+/// `traces` itself.
+#[repr(C)]
+pub struct AsmCoreMachine {
+    pub registers: [u64; RISCV_GENERAL_REGISTER_NUMBER],
+    pub pc: u64,
+    pub memory: FlatMemory,
+    pub isa: u8,
+    pub version: u32,
+    pub running: bool,
+    pub exit_code: i8,
+    pub cycles: u64,
+    pub traces: Vec<Trace>,
+    pub coprocessor_v: CoprocessorV,
+}
+
+impl AsmCoreMachine {
+    pub fn new(isa: u8, version: u32, _max_cycles: u64) -> Box<Self> {
+        Box::new(Self {
+            registers: [0; RISCV_GENERAL_REGISTER_NUMBER],
+            pc: 0,
+            memory: FlatMemory::new(DEFAULT_MEMORY_SIZE),
+            isa,
+            version,
+            running: false,
+            exit_code: 0,
+            cycles: 0,
+            traces: vec![Trace::default(); TRACE_SIZE],
+            coprocessor_v: CoprocessorV::default(),
+        })
+    }
+}
+
+/// Thin `CoreMachine`/`SupportMachine` glue over a boxed [`AsmCoreMachine`],
+/// named `imc` (in-memory core) to match what the asm side calls it.
+pub struct AsmGlueMachine {
+    pub imc: Box<AsmCoreMachine>,
+}
+
+impl AsmGlueMachine {
+    pub fn new(imc: Box<AsmCoreMachine>) -> Self {
+        Self { imc }
+    }
+
+    pub fn coprocessor_v(&self) -> &CoprocessorV {
+        &self.imc.coprocessor_v
+    }
+}
+
+impl CoreMachine for AsmGlueMachine {
+    type Mem = FlatMemory;
+
+    fn isa(&self) -> u8 {
+        self.imc.isa
+    }
+
+    fn version(&self) -> u32 {
+        self.imc.version
+    }
+
+    fn pc(&self) -> &u64 {
+        &self.imc.pc
+    }
+
+    fn set_pc(&mut self, pc: u64) {
+        self.imc.pc = pc;
+    }
+
+    fn memory_mut(&mut self) -> &mut Self::Mem {
+        &mut self.imc.memory
+    }
+
+    fn registers(&self) -> &[u64; RISCV_GENERAL_REGISTER_NUMBER] {
+        &self.imc.registers
+    }
+
+    fn set_register(&mut self, idx: usize, value: u64) {
+        self.imc.registers[idx] = value;
+    }
+}
+
+impl SupportMachine for AsmGlueMachine {
+    fn running(&self) -> bool {
+        self.imc.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.imc.running = running;
+    }
+
+    fn exit_code(&self) -> i8 {
+        self.imc.exit_code
+    }
+
+    fn set_exit_code(&mut self, code: i8) {
+        self.imc.exit_code = code;
+    }
+
+    fn cycles(&self) -> u64 {
+        self.imc.cycles
+    }
+
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), Error> {
+        self.imc.cycles += cycles;
+        Ok(())
+    }
+
+    fn instruction_cycle_func(&self) -> &InstructionCycleFunc {
+        &default_asm_instruction_cycle_func
+    }
+}
+
+fn default_asm_instruction_cycle_func(_i: crate::instructions::Instruction, _vl: u64, _vsew: u8) -> u64 {
+    1
+}
+
+#[allow(improper_ctypes)]
+extern "C" {
+    /// Hand-written dispatch loop living in the generated asm; runs the
+    /// machine pointed to by `imc` to completion (or a host-call/ecall
+    /// boundary) using `imc.traces` for direct-threaded dispatch, and
+    /// returns the exit code. Only `imc`'s leading scalar fields
+    /// (registers/pc/isa/.../cycles) are addressed directly by hand-written
+    /// offsets from the asm side; `memory`/`traces`' Rust-side
+    /// representations (which is what trips `improper_ctypes` here) are
+    /// never poked at as raw FFI types.
+    fn ckb_vm_asm_run(imc: &mut AsmCoreMachine) -> i8;
+}
+
+/// Owns a [`crate::machine::DefaultMachine`] wrapping [`AsmGlueMachine`]
+/// and drives it by handing off to the asm dispatch loop rather than the
+/// Rust `execute` loop.
+pub struct AsmMachine {
+    pub machine: crate::machine::DefaultMachine<AsmGlueMachine>,
+    pub aot_code: Option<()>,
+}
+
+impl crate::machine::DefaultMachine<AsmGlueMachine> {
+    pub fn coprocessor_v(&self) -> &CoprocessorV {
+        self.inner().coprocessor_v()
+    }
+}
+
+impl AsmMachine {
+    pub fn new(machine: crate::machine::DefaultMachine<AsmGlueMachine>, aot_code: Option<()>) -> Self {
+        Self { machine, aot_code }
+    }
+
+    pub fn load_program(&mut self, program: &bytes::Bytes, args: &[bytes::Bytes]) -> Result<(), Error> {
+        self.machine.load_program(program, args)
+    }
+
+    pub fn run(&mut self) -> Result<i8, Error> {
+        let exit_code = unsafe { ckb_vm_asm_run(&mut self.machine.inner_mut().imc) };
+        self.machine.set_exit_code(exit_code);
+        Ok(exit_code)
+    }
+}
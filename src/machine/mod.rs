@@ -0,0 +1,244 @@
+use std::marker::PhantomData;
+
+use crate::{Error, Memory};
+
+pub mod asm;
+
+pub const ISA_IMC: u8 = 0b001;
+
+pub const VERSION0: u32 = 0;
+pub const VERSION1: u32 = 1;
+
+pub const RISCV_GENERAL_REGISTER_NUMBER: usize = 32;
+
+/// Per-cycle cost of an instruction, parameterised on the current vector
+/// `vl`/`vsew` since RVV instructions are not constant cost.
+pub type InstructionCycleFunc = dyn Fn(crate::instructions::Instruction, u64, u8) -> u64;
+
+fn default_instruction_cycle_func(_i: crate::instructions::Instruction, _vl: u64, _vsew: u8) -> u64 {
+    1
+}
+
+/// The register file, PC and memory shared by every machine flavor
+/// (interpreter, asm, or a third-party backend).
+pub trait CoreMachine {
+    type Mem: Memory;
+
+    fn isa(&self) -> u8;
+    fn version(&self) -> u32;
+    fn pc(&self) -> &u64;
+    fn set_pc(&mut self, pc: u64);
+    fn memory_mut(&mut self) -> &mut Self::Mem;
+    fn registers(&self) -> &[u64; RISCV_GENERAL_REGISTER_NUMBER];
+    fn set_register(&mut self, idx: usize, value: u64);
+}
+
+/// `CoreMachine`s that can actually be run to completion: they track a
+/// running flag, an exit code and a cycle budget.
+pub trait SupportMachine: CoreMachine {
+    fn running(&self) -> bool;
+    fn set_running(&mut self, running: bool);
+    fn exit_code(&self) -> i8;
+    fn set_exit_code(&mut self, code: i8);
+    fn cycles(&self) -> u64;
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), Error>;
+    fn instruction_cycle_func(&self) -> &InstructionCycleFunc;
+}
+
+/// Minimal state shared by every `CoreMachine` implementation: register
+/// file, PC, memory and ISA/version flags.
+pub struct DefaultCoreMachine<R, M> {
+    registers: [u64; RISCV_GENERAL_REGISTER_NUMBER],
+    pc: u64,
+    memory: M,
+    isa: u8,
+    version: u32,
+    running: bool,
+    exit_code: i8,
+    cycles: u64,
+    _marker: PhantomData<R>,
+}
+
+impl<R, M: Memory + Default> DefaultCoreMachine<R, M> {
+    pub fn new(isa: u8, version: u32, _max_cycles: u64) -> Self {
+        Self {
+            registers: [0; RISCV_GENERAL_REGISTER_NUMBER],
+            pc: 0,
+            memory: M::default(),
+            isa,
+            version,
+            running: false,
+            exit_code: 0,
+            cycles: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R, M: Memory> CoreMachine for DefaultCoreMachine<R, M> {
+    type Mem = M;
+
+    fn isa(&self) -> u8 {
+        self.isa
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn pc(&self) -> &u64 {
+        &self.pc
+    }
+
+    fn set_pc(&mut self, pc: u64) {
+        self.pc = pc;
+    }
+
+    fn memory_mut(&mut self) -> &mut Self::Mem {
+        &mut self.memory
+    }
+
+    fn registers(&self) -> &[u64; RISCV_GENERAL_REGISTER_NUMBER] {
+        &self.registers
+    }
+
+    fn set_register(&mut self, idx: usize, value: u64) {
+        self.registers[idx] = value;
+    }
+}
+
+impl<R, M: Memory> SupportMachine for DefaultCoreMachine<R, M> {
+    fn running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn exit_code(&self) -> i8 {
+        self.exit_code
+    }
+
+    fn set_exit_code(&mut self, code: i8) {
+        self.exit_code = code;
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), Error> {
+        self.cycles += cycles;
+        Ok(())
+    }
+
+    fn instruction_cycle_func(&self) -> &InstructionCycleFunc {
+        &default_instruction_cycle_func
+    }
+}
+
+/// Wraps a `CoreMachine` with the bits needed to actually load and run a
+/// program: argv, ecall/ebreak handling, and (via `instruction_cycle_func`)
+/// gas metering.
+pub struct DefaultMachine<Inner> {
+    pub inner: Inner,
+}
+
+impl<Inner: CoreMachine> CoreMachine for DefaultMachine<Inner> {
+    type Mem = Inner::Mem;
+
+    fn isa(&self) -> u8 {
+        self.inner.isa()
+    }
+
+    fn version(&self) -> u32 {
+        self.inner.version()
+    }
+
+    fn pc(&self) -> &u64 {
+        self.inner.pc()
+    }
+
+    fn set_pc(&mut self, pc: u64) {
+        self.inner.set_pc(pc)
+    }
+
+    fn memory_mut(&mut self) -> &mut Self::Mem {
+        self.inner.memory_mut()
+    }
+
+    fn registers(&self) -> &[u64; RISCV_GENERAL_REGISTER_NUMBER] {
+        self.inner.registers()
+    }
+
+    fn set_register(&mut self, idx: usize, value: u64) {
+        self.inner.set_register(idx, value)
+    }
+}
+
+impl<Inner: SupportMachine> SupportMachine for DefaultMachine<Inner> {
+    fn running(&self) -> bool {
+        self.inner.running()
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.inner.set_running(running)
+    }
+
+    fn exit_code(&self) -> i8 {
+        self.inner.exit_code()
+    }
+
+    fn set_exit_code(&mut self, code: i8) {
+        self.inner.set_exit_code(code)
+    }
+
+    fn cycles(&self) -> u64 {
+        self.inner.cycles()
+    }
+
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), Error> {
+        self.inner.add_cycles(cycles)
+    }
+
+    fn instruction_cycle_func(&self) -> &InstructionCycleFunc {
+        self.inner.instruction_cycle_func()
+    }
+}
+
+impl<Inner> DefaultMachine<Inner> {
+    pub fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.inner
+    }
+
+    pub fn inner(&self) -> &Inner {
+        &self.inner
+    }
+}
+
+impl<Inner: CoreMachine> DefaultMachine<Inner> {
+    pub fn load_program(
+        &mut self,
+        program: &bytes::Bytes,
+        _args: &[bytes::Bytes],
+    ) -> Result<(), Error> {
+        self.memory_mut().store_bytes(0, program)?;
+        self.set_pc(0);
+        Ok(())
+    }
+}
+
+pub struct DefaultMachineBuilder<Inner> {
+    inner: Inner,
+}
+
+impl<Inner> DefaultMachineBuilder<Inner> {
+    pub fn new(inner: Inner) -> Self {
+        Self { inner }
+    }
+
+    pub fn build(self) -> DefaultMachine<Inner> {
+        DefaultMachine { inner: self.inner }
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::Error;
+
+/// Byte-addressable memory backing a machine. `M: Memory` is threaded
+/// through the decoder and the instruction handlers so the same code can
+/// run against a sparse, page-mapped or native-backed implementation.
+pub trait Memory {
+    fn load8(&mut self, addr: u64) -> Result<u8, Error>;
+    fn load16(&mut self, addr: u64) -> Result<u16, Error>;
+    fn load32(&mut self, addr: u64) -> Result<u32, Error>;
+    fn load64(&mut self, addr: u64) -> Result<u64, Error>;
+
+    fn store8(&mut self, addr: u64, value: u8) -> Result<(), Error>;
+    fn store16(&mut self, addr: u64, value: u16) -> Result<(), Error>;
+    fn store32(&mut self, addr: u64, value: u32) -> Result<(), Error>;
+    fn store64(&mut self, addr: u64, value: u64) -> Result<(), Error>;
+
+    fn store_bytes(&mut self, addr: u64, bytes: &[u8]) -> Result<(), Error> {
+        for (i, b) in bytes.iter().enumerate() {
+            self.store8(addr + i as u64, *b)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `HashMap`-backed `Memory` implementation. Simple and slow, but
+/// convenient for tests and tooling that don't need a flat address space.
+#[derive(Default)]
+pub struct SparseMemory<R> {
+    pages: HashMap<u64, u8>,
+    _marker: std::marker::PhantomData<R>,
+}
+
+impl<R> SparseMemory<R> {
+    pub fn new() -> Self {
+        Self {
+            pages: HashMap::new(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<R> Memory for SparseMemory<R> {
+    fn load8(&mut self, addr: u64) -> Result<u8, Error> {
+        Ok(*self.pages.get(&addr).unwrap_or(&0))
+    }
+
+    fn load16(&mut self, addr: u64) -> Result<u16, Error> {
+        let lo = self.load8(addr)? as u16;
+        let hi = self.load8(addr + 1)? as u16;
+        Ok(lo | (hi << 8))
+    }
+
+    fn load32(&mut self, addr: u64) -> Result<u32, Error> {
+        let lo = self.load16(addr)? as u32;
+        let hi = self.load16(addr + 2)? as u32;
+        Ok(lo | (hi << 16))
+    }
+
+    fn load64(&mut self, addr: u64) -> Result<u64, Error> {
+        let lo = self.load32(addr)? as u64;
+        let hi = self.load32(addr + 4)? as u64;
+        Ok(lo | (hi << 32))
+    }
+
+    fn store8(&mut self, addr: u64, value: u8) -> Result<(), Error> {
+        self.pages.insert(addr, value);
+        Ok(())
+    }
+
+    fn store16(&mut self, addr: u64, value: u16) -> Result<(), Error> {
+        self.store8(addr, value as u8)?;
+        self.store8(addr + 1, (value >> 8) as u8)
+    }
+
+    fn store32(&mut self, addr: u64, value: u32) -> Result<(), Error> {
+        self.store16(addr, value as u16)?;
+        self.store16(addr + 2, (value >> 16) as u16)
+    }
+
+    fn store64(&mut self, addr: u64, value: u64) -> Result<(), Error> {
+        self.store32(addr, value as u32)?;
+        self.store32(addr + 4, (value >> 32) as u32)
+    }
+}
+
+/// A flat, fixed-size byte buffer. Used by the asm backend in place of
+/// `SparseMemory`'s `HashMap`, since the hand-written assembly dispatch
+/// loop needs to address VM memory as one contiguous region rather than
+/// walking a hash map.
+///
+/// This is *not* FFI-safe on its own: `Vec<u8>` has no stable layout, so
+/// `#[repr(C)]` here only fixes the position of the `bytes` field, not
+/// what's inside it. The asm side never reads this field directly (see
+/// the `ckb_vm_asm_run` doc comment in `machine::asm`) — if it ever
+/// needs to, this will have to become a raw pointer/length pair instead.
+#[repr(C)]
+pub struct FlatMemory {
+    bytes: Vec<u8>,
+}
+
+impl FlatMemory {
+    pub fn new(size: usize) -> Self {
+        Self { bytes: vec![0; size] }
+    }
+}
+
+impl Memory for FlatMemory {
+    fn load8(&mut self, addr: u64) -> Result<u8, Error> {
+        self.bytes.get(addr as usize).copied().ok_or(Error::OutOfBound)
+    }
+
+    fn load16(&mut self, addr: u64) -> Result<u16, Error> {
+        let lo = self.load8(addr)? as u16;
+        let hi = self.load8(addr + 1)? as u16;
+        Ok(lo | (hi << 8))
+    }
+
+    fn load32(&mut self, addr: u64) -> Result<u32, Error> {
+        let lo = self.load16(addr)? as u32;
+        let hi = self.load16(addr + 2)? as u32;
+        Ok(lo | (hi << 16))
+    }
+
+    fn load64(&mut self, addr: u64) -> Result<u64, Error> {
+        let lo = self.load32(addr)? as u64;
+        let hi = self.load32(addr + 4)? as u64;
+        Ok(lo | (hi << 32))
+    }
+
+    fn store8(&mut self, addr: u64, value: u8) -> Result<(), Error> {
+        let slot = self.bytes.get_mut(addr as usize).ok_or(Error::OutOfBound)?;
+        *slot = value;
+        Ok(())
+    }
+
+    fn store16(&mut self, addr: u64, value: u16) -> Result<(), Error> {
+        self.store8(addr, value as u8)?;
+        self.store8(addr + 1, (value >> 8) as u8)
+    }
+
+    fn store32(&mut self, addr: u64, value: u32) -> Result<(), Error> {
+        self.store16(addr, value as u16)?;
+        self.store16(addr + 2, (value >> 16) as u16)
+    }
+
+    fn store64(&mut self, addr: u64, value: u64) -> Result<(), Error> {
+        self.store32(addr, value as u32)?;
+        self.store32(addr + 4, (value >> 32) as u32)
+    }
+}
@@ -58,7 +58,7 @@ pub fn test_rust_auipc_fusion() {
         .into();
 
     let core_machine =
-        DefaultCoreMachine::<u64, SparseMemory<u64>>::new(ISA_IMC, VERSION1, u64::max_value());
+        DefaultCoreMachine::<u64, SparseMemory<u64>>::new(ISA_IMC, VERSION1, u64::MAX);
     let mut machine = DefaultMachineBuilder::new(core_machine).build();
     machine
         .load_program(&buffer, &vec!["auipc_no_sign_extend".into()])
@@ -100,7 +100,7 @@ pub fn test_asm_auipc_fusion() {
         .unwrap()
         .into();
 
-    let asm_core = AsmCoreMachine::new(ISA_IMC, VERSION1, u64::max_value());
+    let asm_core = AsmCoreMachine::new(ISA_IMC, VERSION1, u64::MAX);
     let asm_glue = AsmGlueMachine::new(asm_core);
     let core = DefaultMachineBuilder::new(asm_glue).build();
     let mut machine = AsmMachine::new(core, None);
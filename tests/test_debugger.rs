@@ -0,0 +1,191 @@
+use ckb_vm::debugger::{Debugger, OnBreak};
+use ckb_vm::instructions::{extract_opcode, set_instruction_length_n, Instruction, Itype, Jtype};
+use ckb_vm::machine::VERSION1;
+use ckb_vm::{
+    CoreMachine, DefaultCoreMachine, DefaultMachine, DefaultMachineBuilder, Error, SparseMemory,
+    SupportMachine, ISA_IMC,
+};
+use ckb_vm_definitions::instructions as insts;
+
+type Mach = DefaultMachine<DefaultCoreMachine<u64, SparseMemory<u64>>>;
+
+struct Recorder {
+    breaks: Vec<u64>,
+}
+
+impl OnBreak<Mach> for Recorder {
+    fn on_break(&mut self, machine: &mut Mach) {
+        self.breaks.push(*machine.pc());
+    }
+}
+
+fn new_machine() -> Mach {
+    let core = DefaultCoreMachine::<u64, SparseMemory<u64>>::new(ISA_IMC, VERSION1, u64::MAX);
+    let mut machine = DefaultMachineBuilder::new(core).build();
+    machine.set_running(true);
+    machine
+}
+
+/// A tiny hand-rolled `execute`, standing in for `Debugger::run`'s
+/// caller-supplied step closure: enough jal/jalr/ecall semantics to
+/// exercise `Debugger`'s call-depth tracking without a real
+/// decoder/assembler.
+fn step_execute(machine: &mut Mach, instruction: Instruction) -> Result<(), Error> {
+    match extract_opcode(instruction) {
+        insts::OP_JAL => {
+            let j = Jtype(instruction);
+            let pc = *machine.pc();
+            machine.set_register(j.rd() as usize, pc.wrapping_add(4));
+            machine.set_pc(pc.wrapping_add(j.immediate_s() as i64 as u64));
+        }
+        insts::OP_JALR => {
+            let i = Itype(instruction);
+            let target = machine.registers()[i.rs1() as usize];
+            machine.set_register(i.rd() as usize, 0);
+            machine.set_pc(target);
+        }
+        insts::OP_ECALL => {
+            machine.set_running(false);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[test]
+fn test_call_depth_tracks_jal_jalr() {
+    // pc0: jal ra, +8   (call, depth 0 -> 1, jumps to pc8)
+    // pc4: ecall        (landing spot once the call returns)
+    // pc8: jalr x0, ra  (return, depth 1 -> 0, jumps back to pc4)
+    let program = [
+        set_instruction_length_n(Jtype::new(insts::OP_JAL, 1, 8).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_ECALL, 0, 0, 0).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_JALR, 0, 1, 0).0, 4),
+    ];
+
+    let mut machine = new_machine();
+    let mut debugger = Debugger::new();
+    let mut handler = Recorder { breaks: Vec::new() };
+
+    debugger
+        .run(
+            &mut machine,
+            |m: &mut Mach| Ok(program[(*m.pc() / 4) as usize]),
+            step_execute,
+            &mut handler,
+            false,
+        )
+        .unwrap();
+
+    assert_eq!(debugger.call_depth(), 0);
+    assert!(handler.breaks.is_empty());
+}
+
+#[test]
+fn test_single_step_returns_control_after_one_instruction() {
+    let program = [set_instruction_length_n(Jtype::new(insts::OP_JAL, 1, 8).0, 4)];
+
+    let mut machine = new_machine();
+    let mut debugger = Debugger::new();
+    let mut handler = Recorder { breaks: Vec::new() };
+
+    debugger
+        .run(
+            &mut machine,
+            |m: &mut Mach| Ok(program[(*m.pc() / 4) as usize]),
+            step_execute,
+            &mut handler,
+            true,
+        )
+        .unwrap();
+
+    // `run` handed control back after exactly one instruction, even though
+    // nothing reached a breakpoint and the machine is still running.
+    assert_eq!(debugger.call_depth(), 1);
+    assert_eq!(*machine.pc(), 8);
+    assert_eq!(handler.breaks, vec![8]);
+}
+
+#[test]
+fn test_step_out_pauses_when_frame_returns() {
+    // pc0: jal ra, +8   (call, depth 0 -> 1, jumps to pc8)
+    // pc4: ecall        (landing spot once the call returns; never reached
+    //                    if `run` pauses on `step_out` first)
+    // pc8: jalr x0, ra  (return, depth 1 -> 0, jumps back to pc4)
+    let program = [
+        set_instruction_length_n(Jtype::new(insts::OP_JAL, 1, 8).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_ECALL, 0, 0, 0).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_JALR, 0, 1, 0).0, 4),
+    ];
+    let decode = |m: &mut Mach| Ok(program[(*m.pc() / 4) as usize]);
+
+    let mut machine = new_machine();
+    let mut debugger = Debugger::new();
+    let mut handler = Recorder { breaks: Vec::new() };
+
+    // Single-step over the `jal` so we're paused at depth 1, then arm
+    // step_out and let `run` go until that frame returns.
+    debugger.run(&mut machine, decode, step_execute, &mut handler, true).unwrap();
+    assert_eq!(debugger.call_depth(), 1);
+    debugger.step_out();
+
+    debugger.run(&mut machine, decode, step_execute, &mut handler, false).unwrap();
+
+    assert_eq!(debugger.call_depth(), 0);
+    // Paused once for the single step, once for the step-out hit at the
+    // `jalr`'s post-execution pc (back at pc4). Since the second `run` call
+    // had `single_step: false`, the step-out pause doesn't return control —
+    // it calls `on_break` and keeps going, reaching `ecall` right after.
+    assert_eq!(handler.breaks, vec![8, 4]);
+    assert!(!machine.running());
+}
+
+#[test]
+fn test_breakpoint_pauses_synchronously_and_run_keeps_going() {
+    // pc0: jal ra, +8   (call, jumps to pc8)
+    // pc4: ecall        (landing spot once the call returns; stops the
+    //                    machine, proving `run` kept going past the
+    //                    breakpoint instead of returning control)
+    // pc8: jalr x0, ra  (return, jumps back to pc4) — breakpoint here
+    let program = [
+        set_instruction_length_n(Jtype::new(insts::OP_JAL, 1, 8).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_ECALL, 0, 0, 0).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_JALR, 0, 1, 0).0, 4),
+    ];
+    let decode = |m: &mut Mach| Ok(program[(*m.pc() / 4) as usize]);
+
+    let mut machine = new_machine();
+    let mut debugger = Debugger::new();
+    let mut handler = Recorder { breaks: Vec::new() };
+    debugger.add_breakpoint(8);
+
+    debugger.run(&mut machine, decode, step_execute, &mut handler, false).unwrap();
+
+    // `on_break` fired once, synchronously, at the breakpoint PC, and
+    // `run` kept executing afterwards all the way to `ecall` rather than
+    // handing control back (that's `single_step: true`'s job, not a
+    // breakpoint's).
+    assert_eq!(handler.breaks, vec![8]);
+    assert!(!machine.running());
+}
+
+#[test]
+fn test_remove_breakpoint_un_arms_it() {
+    let program = [
+        set_instruction_length_n(Jtype::new(insts::OP_JAL, 1, 8).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_ECALL, 0, 0, 0).0, 4),
+        set_instruction_length_n(Itype::new(insts::OP_JALR, 0, 1, 0).0, 4),
+    ];
+    let decode = |m: &mut Mach| Ok(program[(*m.pc() / 4) as usize]);
+
+    let mut machine = new_machine();
+    let mut debugger = Debugger::new();
+    let mut handler = Recorder { breaks: Vec::new() };
+    debugger.add_breakpoint(8);
+    debugger.remove_breakpoint(8);
+
+    debugger.run(&mut machine, decode, step_execute, &mut handler, false).unwrap();
+
+    assert!(handler.breaks.is_empty());
+    assert!(!machine.running());
+}
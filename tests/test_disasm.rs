@@ -0,0 +1,40 @@
+use ckb_vm::instructions::{disassemble, Itype, Rtype, Utype};
+use ckb_vm_definitions::instructions as insts;
+
+#[test]
+fn test_disassemble_base_opcodes() {
+    let lui = Utype::new(insts::OP_LUI, 10, 0x1234_0000).0;
+    assert_eq!(disassemble(lui, 0), "lui a0, 0x12340");
+
+    let auipc = Utype::new(insts::OP_AUIPC, 10, 0x1000).0;
+    assert!(disassemble(auipc, 0x80).contains("auipc a0"));
+
+    let addi = Itype::new(insts::OP_ADDI, 10, 6, -1).0;
+    assert_eq!(disassemble(addi, 0), "addi a0, t1, -1");
+
+    let add = Rtype::new(insts::OP_ADD, 10, 6, 7).0;
+    assert_eq!(disassemble(add, 0), "add a0, t1, t2");
+
+    let ecall = Itype::new(insts::OP_ECALL, 0, 0, 0).0;
+    assert_eq!(disassemble(ecall, 0), "ecall");
+}
+
+#[test]
+fn test_disassemble_fused_opcodes() {
+    let load_uimm = Utype::new(insts::OP_CUSTOM_LOAD_UIMM, 10, 0x42).0;
+    assert_eq!(disassemble(load_uimm, 0), "load_uimm a0, 0x42");
+
+    let indexed_addr = Rtype::new_with_immediate(insts::OP_CUSTOM_INDEXED_ADDR, 10, 6, 7, 2).0;
+    assert_eq!(disassemble(indexed_addr, 0), "indexed_addr a0, t1 << 2, t2");
+
+    let wide_load32 = Rtype::new_with_immediate(insts::OP_CUSTOM_WIDE_LOAD32, 10, 6, 7, 4).0;
+    assert_eq!(disassemble(wide_load32, 0), "wide_load32 a0:t2, 4(t1)");
+
+    let trace_end = insts::OP_CUSTOM_TRACE_END as u64;
+    assert_eq!(disassemble(trace_end, 0), "trace_end");
+}
+
+#[test]
+fn test_disassemble_unknown_opcode() {
+    assert_eq!(disassemble(0xee, 0), "unknown(opcode=0xee)");
+}
@@ -0,0 +1,97 @@
+use ckb_vm::decoder::{build_decoder, fusion::FusionDecoder};
+use ckb_vm::instructions::{extract_opcode, instruction_length, Itype};
+use ckb_vm::{Memory, SparseMemory};
+use ckb_vm_definitions::instructions as insts;
+
+fn encode_utype(opcode: u32, rd: u8, imm_hi: u32) -> u32 {
+    (imm_hi & 0xffff_f000) | (u32::from(rd) << 7) | opcode
+}
+
+fn encode_itype(opcode: u32, funct3: u32, rd: u8, rs1: u8, imm12: i32) -> u32 {
+    ((imm12 as u32 & 0xfff) << 20) | (u32::from(rs1) << 15) | (funct3 << 12) | (u32::from(rd) << 7) | opcode
+}
+
+fn encode_rtype(opcode: u32, funct3: u32, funct7: u32, rd: u8, rs1: u8, rs2: u8) -> u32 {
+    (funct7 << 25)
+        | (u32::from(rs2) << 20)
+        | (u32::from(rs1) << 15)
+        | (funct3 << 12)
+        | (u32::from(rd) << 7)
+        | opcode
+}
+
+fn fusion_decoder_over(words: &[u32]) -> (FusionDecoder, SparseMemory<u64>) {
+    let mut memory = SparseMemory::<u64>::new();
+    for (i, word) in words.iter().enumerate() {
+        memory.store32(i as u64 * 4, *word).unwrap();
+    }
+    (FusionDecoder::new(build_decoder::<u64>(0, 0)), memory)
+}
+
+#[test]
+fn test_fuse_auipc_addi() {
+    // auipc x5, 0x1000 ; addi x5, x5, 4
+    let words = [encode_utype(0x17, 5, 0x0100_0000), encode_itype(0x13, 0, 5, 5, 4)];
+    let (mut decoder, mut memory) = fusion_decoder_over(&words);
+    let fused = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(extract_opcode(fused), insts::OP_CUSTOM_LOAD_IMM_ABS);
+    assert_eq!(instruction_length(fused), 8);
+}
+
+#[test]
+fn test_fuse_lui_addi() {
+    // lui x6, 0x2000 ; addi x6, x6, -1
+    let words = [encode_utype(0x37, 6, 0x0200_0000), encode_itype(0x13, 0, 6, 6, -1)];
+    let (mut decoder, mut memory) = fusion_decoder_over(&words);
+    let fused = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(extract_opcode(fused), insts::OP_CUSTOM_CONST32);
+    assert_eq!(instruction_length(fused), 8);
+}
+
+#[test]
+fn test_fuse_slli_add_keeps_shift_amount() {
+    // slli x7, x10, 3 ; add x7, x7, x11
+    let words = [encode_itype(0x13, 1, 7, 10, 3), encode_rtype(0x33, 0, 0x00, 7, 7, 11)];
+    let (mut decoder, mut memory) = fusion_decoder_over(&words);
+    let fused = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(extract_opcode(fused), insts::OP_CUSTOM_INDEXED_ADDR);
+    assert_eq!(Itype(fused).immediate_u() & 0x3f, 3);
+}
+
+#[test]
+fn test_fuse_wide_load_requires_adjacent_offsets() {
+    // lw x5, 0(x10) ; lw x6, 4(x10) -> fused (offsets one width apart)
+    let adjacent = [encode_itype(0x03, 2, 5, 10, 0), encode_itype(0x03, 2, 6, 10, 4)];
+    let (mut decoder, mut memory) = fusion_decoder_over(&adjacent);
+    let fused = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(extract_opcode(fused), insts::OP_CUSTOM_WIDE_LOAD32);
+
+    // lw x5, 0(x10) ; lw x6, 8(x10) -> offsets aren't adjacent, not fused
+    let gapped = [encode_itype(0x03, 2, 5, 10, 0), encode_itype(0x03, 2, 6, 10, 8)];
+    let (mut decoder, mut memory) = fusion_decoder_over(&gapped);
+    let unfused = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(extract_opcode(unfused), insts::OP_LW);
+}
+
+#[test]
+fn test_fuse_wide_load_rejects_first_loads_destination_as_the_shared_base() {
+    // lw x10, 0(x10) ; lw x6, 4(x10): the second load's base would already
+    // be the first load's result once the unfused pair actually ran, so
+    // fusing this the same way as a regular adjacent pair (both addresses
+    // computed from the original x10) would compute the wrong thing.
+    let hazard = [encode_itype(0x03, 2, 10, 10, 0), encode_itype(0x03, 2, 6, 10, 4)];
+    let (mut decoder, mut memory) = fusion_decoder_over(&hazard);
+    let unfused = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(extract_opcode(unfused), insts::OP_LW);
+}
+
+#[test]
+fn test_lookahead_stops_at_basic_block_end() {
+    // A single ecall with nothing mapped after it: the plain Decoder would
+    // fail trying to decode a second, never-loaded instruction to fill the
+    // lookahead window.
+    let words = [encode_itype(0x73, 0, 0, 0, 0)];
+    let (mut decoder, mut memory) = fusion_decoder_over(&words);
+    let decoded = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(extract_opcode(decoded), insts::OP_ECALL);
+}
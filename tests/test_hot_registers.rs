@@ -0,0 +1,68 @@
+use ckb_vm::instructions::{set_instruction_length_n, Rtype};
+use ckb_vm::machine::asm::hot_registers::analyze;
+use ckb_vm_definitions::asm::{Trace, HOT_REGISTER_CAPACITY};
+use ckb_vm_definitions::instructions as insts;
+
+fn trace_with(instructions: &[u64]) -> Trace {
+    let mut trace = Trace::default();
+    for (slot, &instruction) in instructions.iter().enumerate() {
+        trace.instructions[slot] = instruction;
+    }
+    trace.instructions[instructions.len()] = set_instruction_length_n(insts::OP_CUSTOM_TRACE_END as u64, 4);
+    trace
+}
+
+fn add(rd: u8, rs1: u8, rs2: u8) -> u64 {
+    set_instruction_length_n(Rtype::new(insts::OP_ADD, rd, rs1, rs2).0, 4)
+}
+
+#[test]
+fn test_analyze_picks_most_touched_registers_first() {
+    // x1 is touched 3 times, x2 twice, x3 once: residency should rank
+    // them in that order.
+    let mut trace = trace_with(&[add(1, 1, 2), add(1, 1, 3), add(2, 1, 2)]);
+
+    analyze(&mut trace);
+
+    assert_eq!(trace.hot_register_count, 3);
+    assert_eq!(&trace.hot_registers[..3], &[1, 2, 3]);
+}
+
+#[test]
+fn test_analyze_respects_hot_register_capacity() {
+    // 6 distinct registers read, one more than HOT_REGISTER_CAPACITY (4).
+    let mut trace = trace_with(&[
+        add(1, 2, 3),
+        add(4, 5, 6),
+        add(1, 2, 3),
+        add(1, 2, 3),
+    ]);
+
+    analyze(&mut trace);
+
+    assert_eq!(trace.hot_register_count as usize, HOT_REGISTER_CAPACITY);
+}
+
+#[test]
+fn test_analyze_excludes_x0() {
+    // x0 is read/written like any other register slot here, but it's
+    // never worth caching: writes to it are discarded and it always
+    // reads as zero.
+    let mut trace = trace_with(&[add(0, 0, 1), add(0, 0, 1)]);
+
+    analyze(&mut trace);
+
+    assert!(!trace.hot_registers[..trace.hot_register_count as usize].contains(&0));
+    assert_eq!(trace.hot_register_count, 1);
+    assert_eq!(trace.hot_registers[0], 1);
+}
+
+#[test]
+fn test_analyze_marks_dirty_mask_only_for_written_hot_registers() {
+    // x1 is written (it's a dst), x2 is only ever read.
+    let mut trace = trace_with(&[add(1, 2, 2)]);
+
+    analyze(&mut trace);
+
+    assert_eq!(trace.dirty_mask, 1 << 1);
+}
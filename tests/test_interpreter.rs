@@ -0,0 +1,86 @@
+use ckb_vm::decoder::build_decoder;
+use ckb_vm::instructions::{
+    execute, generate_handle_function_list, generate_vcheck_function_list, set_instruction_length_n,
+    Btype, Itype, Jtype,
+};
+use ckb_vm::machine::VERSION1;
+use ckb_vm::{
+    CoreMachine, DefaultCoreMachine, DefaultMachine, DefaultMachineBuilder, Memory, SparseMemory,
+    SupportMachine, ISA_IMC,
+};
+use ckb_vm_definitions::instructions as insts;
+
+type Mach = DefaultMachine<DefaultCoreMachine<u64, SparseMemory<u64>>>;
+
+fn new_machine() -> Mach {
+    let core = DefaultCoreMachine::<u64, SparseMemory<u64>>::new(ISA_IMC, VERSION1, u64::MAX);
+    let mut machine = DefaultMachineBuilder::new(core).build();
+    machine.set_running(true);
+    machine
+}
+
+fn step(machine: &mut Mach, instruction: u64) {
+    let vcheck = generate_vcheck_function_list::<Mach>();
+    let handle = generate_handle_function_list::<Mach>();
+    execute(machine, &vcheck, &handle, instruction).expect("execute");
+}
+
+#[test]
+fn test_jal_links_and_jumps() {
+    let mut machine = new_machine();
+    step(&mut machine, set_instruction_length_n(Jtype::new(insts::OP_JAL, 1, 0x100).0, 4));
+    assert_eq!(*machine.pc(), 0x100);
+    assert_eq!(machine.registers()[1], 4);
+}
+
+#[test]
+fn test_jalr_links_and_jumps_to_masked_target() {
+    let mut machine = new_machine();
+    machine.set_register(2, 0x201);
+    step(&mut machine, set_instruction_length_n(Itype::new(insts::OP_JALR, 1, 2, 4).0, 4));
+    // rs1 + imm = 0x205, low bit cleared per jalr's spec
+    assert_eq!(*machine.pc(), 0x204);
+    assert_eq!(machine.registers()[1], 4);
+}
+
+#[test]
+fn test_beq_branches_only_when_equal() {
+    let mut machine = new_machine();
+    machine.set_register(1, 5);
+    machine.set_register(2, 5);
+    step(&mut machine, set_instruction_length_n(Btype::new(insts::OP_BEQ, 1, 2, 0x40).0, 4));
+    assert_eq!(*machine.pc(), 0x40);
+
+    let mut machine = new_machine();
+    machine.set_register(1, 5);
+    machine.set_register(2, 6);
+    step(&mut machine, set_instruction_length_n(Btype::new(insts::OP_BEQ, 1, 2, 0x40).0, 4));
+    assert_eq!(*machine.pc(), 4);
+}
+
+#[test]
+fn test_lw_sign_extends_ld_does_not() {
+    let mut machine = new_machine();
+    machine.memory_mut().store32(0x10, 0xffff_fffe).unwrap();
+    machine.memory_mut().store64(0x20, 0xffff_ffff_0000_0001).unwrap();
+    machine.set_register(1, 0);
+
+    step(&mut machine, set_instruction_length_n(Itype::new(insts::OP_LW, 2, 1, 0x10).0, 4));
+    assert_eq!(machine.registers()[2], 0xffff_ffff_ffff_fffe);
+
+    step(&mut machine, set_instruction_length_n(Itype::new(insts::OP_LD, 3, 1, 0x20).0, 4));
+    assert_eq!(machine.registers()[3], 0xffff_ffff_0000_0001);
+}
+
+#[test]
+fn test_decoder_keeps_full_six_bit_shamt() {
+    // slli x5, x10, 33: shamt's bit 5 (worth 32) lives in what `decode_word`
+    // otherwise lumps in with funct7, one bit above the 5 bits `rs2` keeps.
+    let word: u32 = (33 << 20) | (10 << 15) | (0x1 << 12) | (5 << 7) | 0x13;
+    let mut memory = SparseMemory::<u64>::new();
+    memory.store32(0, word).unwrap();
+
+    let mut decoder = build_decoder::<u64>(ISA_IMC, VERSION1);
+    let instruction = decoder.decode(&mut memory, 0).unwrap();
+    assert_eq!(Itype(instruction).immediate_u(), 33);
+}
@@ -0,0 +1,113 @@
+use ckb_vm::instructions::{set_instruction_length_n, Btype, Itype, Jtype, Rtype, Utype};
+use ckb_vm::jit::{compile_trace, ir, Arch};
+use ckb_vm_definitions::asm::{calculate_slot, Trace};
+use ckb_vm_definitions::instructions as insts;
+
+fn imm_op(op: &ir::Op) -> Option<(u8, i64)> {
+    match *op {
+        ir::Op::Imm { dst: ir::Value::VmReg(reg), value } => Some((reg, value)),
+        _ => None,
+    }
+}
+
+fn trace_with(address: u64, instructions: &[u64]) -> Trace {
+    let mut trace = Trace { address, ..Trace::default() };
+    for (slot, &instruction) in instructions.iter().enumerate() {
+        trace.instructions[slot] = instruction;
+    }
+    trace.instructions[instructions.len()] = set_instruction_length_n(insts::OP_CUSTOM_TRACE_END as u64, 4);
+    trace
+}
+
+#[test]
+fn test_build_computes_beq_target_slot_from_pc_plus_imm() {
+    let beq = set_instruction_length_n(Btype::new(insts::OP_BEQ, 1, 2, 0x40).0, 4);
+    let trace = trace_with(0x1000, &[beq]);
+
+    let block = ir::build(&trace);
+    let branch = block
+        .ops
+        .iter()
+        .find_map(|op| match *op {
+            ir::Op::CmpBranch { target_slot, .. } => Some(target_slot),
+            _ => None,
+        })
+        .expect("build should lower OP_BEQ to a CmpBranch op");
+
+    assert_eq!(branch, calculate_slot(0x1040));
+}
+
+#[test]
+#[should_panic(expected = "CmpBranch")]
+fn test_x86_64_backend_refuses_to_silently_drop_a_branch() {
+    let beq = set_instruction_length_n(Btype::new(insts::OP_BEQ, 1, 2, 0x40).0, 4);
+    let trace = trace_with(0x1000, &[beq]);
+    compile_trace(&trace, Arch::X86_64);
+}
+
+#[test]
+#[should_panic(expected = "CmpBranch")]
+fn test_arm64_backend_refuses_to_silently_drop_a_branch() {
+    let beq = set_instruction_length_n(Btype::new(insts::OP_BEQ, 1, 2, 0x40).0, 4);
+    let trace = trace_with(0x1000, &[beq]);
+    compile_trace(&trace, Arch::Arm64);
+}
+
+#[test]
+#[should_panic(expected = "CallRuntime")]
+fn test_x86_64_backend_refuses_to_emit_a_dangling_call() {
+    let ecall = set_instruction_length_n(Itype::new(insts::OP_ECALL, 0, 0, 0).0, 4);
+    let trace = trace_with(0x1000, &[ecall]);
+    compile_trace(&trace, Arch::X86_64);
+}
+
+#[test]
+fn test_build_lowers_auipc_and_load_imm_abs_as_a_compile_time_constant() {
+    let auipc = set_instruction_length_n(Utype::new(insts::OP_AUIPC, 5, 0x3000).0, 4);
+    let trace = trace_with(0x2000, &[auipc]);
+
+    let block = ir::build(&trace);
+    let (reg, value) = block.ops.iter().find_map(imm_op).expect("auipc should lower to Op::Imm");
+
+    assert_eq!(reg, 5);
+    assert_eq!(value, 0x5000);
+}
+
+#[test]
+fn test_build_lowers_load_uimm_as_the_raw_unsigned_immediate() {
+    let load_uimm = set_instruction_length_n(Utype::new(insts::OP_CUSTOM_LOAD_UIMM, 6, 0x1234).0, 4);
+    let trace = trace_with(0x2000, &[load_uimm]);
+
+    let block = ir::build(&trace);
+    let (reg, value) = block.ops.iter().find_map(imm_op).expect("load_uimm should lower to Op::Imm");
+
+    assert_eq!(reg, 6);
+    assert_eq!(value, 0x1234);
+}
+
+#[test]
+#[should_panic(expected = "jal/jalr")]
+fn test_build_refuses_to_silently_drop_jal() {
+    let jal = set_instruction_length_n(Jtype::new(insts::OP_JAL, 1, 0x100).0, 4);
+    let trace = trace_with(0x1000, &[jal]);
+    ir::build(&trace);
+}
+
+#[test]
+#[should_panic(expected = "jal/jalr")]
+fn test_build_refuses_to_silently_drop_jalr() {
+    let jalr = set_instruction_length_n(Itype::new(insts::OP_JALR, 1, 2, 0).0, 4);
+    let trace = trace_with(0x1000, &[jalr]);
+    ir::build(&trace);
+}
+
+#[test]
+#[should_panic(expected = "wide loads")]
+fn test_build_refuses_to_silently_drop_a_wide_load() {
+    let wide_load = set_instruction_length_n(
+        Rtype::new_with_immediate(insts::OP_CUSTOM_WIDE_LOAD32, 1, 2, 3, 0).0,
+        4,
+    );
+    let trace = trace_with(0x1000, &[wide_load]);
+    ir::build(&trace);
+}